@@ -0,0 +1,170 @@
+//! Runtime support for the `#[timeout]` attribute on async functions.
+//!
+//! The test future is raced against a timer which runs on a helper thread and therefore works with
+//! every async runtime. If the timer fires first the test future is dropped and thereby cancelled.
+
+use crate::report;
+use crate::retry::{Attempt, Attempts, TimedOut};
+use crate::timeout::{Exceeded, Thresholds, TimeoutSpec, Usage};
+use std::future::Future;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+/// Polls the future until it completes or the budget is exceeded. Panics on timeout. `failure`
+/// describes an output which fails the test.
+pub async fn execute_future_with_timeout<F: Future>(
+    spec: TimeoutSpec,
+    future: F,
+    failure: impl Fn(&F::Output) -> Option<String>,
+) -> F::Output {
+    let mut future = Some(future);
+    execute_future_with_retries(
+        spec,
+        move || {
+            future
+                .take()
+                .expect("Retries require execute_future_with_retries")
+        },
+        failure,
+    )
+    .await
+}
+
+/// Like [execute_future_with_timeout] but creates a new future and polls it again if the previous
+/// one failed or timed out.
+pub async fn execute_future_with_retries<F: Future>(
+    spec: TimeoutSpec,
+    mut create_future: impl FnMut() -> F,
+    failure: impl Fn(&F::Output) -> Option<String>,
+) -> F::Output {
+    let mut attempts = Attempts::new(&spec);
+    loop {
+        let attempt = race(attempts.budget(), create_future()).await;
+        if let Some(output) = attempts.evaluate(attempt, &failure) {
+            return output;
+        }
+    }
+}
+
+// Polls the future and catches its panics. Cancels the future on timeout if a budget is given.
+async fn race<F: Future>(thresholds: Option<&Thresholds>, future: F) -> Attempt<F::Output> {
+    let start = Instant::now();
+    let mut future = Box::pin(future);
+    let mut timer = thresholds
+        .and_then(|thresholds| thresholds.fail)
+        .map(|fail| Box::pin(sleep(fail)));
+    let mut assertion_reported = false;
+    let output = std::future::poll_fn(|cx| {
+        match report::catch_test_panic(|| future.as_mut().poll(cx)) {
+            (Ok(Poll::Ready(output)), _) => return Poll::Ready(Some(Ok(output))),
+            (Ok(Poll::Pending), _) => {}
+            (Err(panic_payload), reported) => {
+                assertion_reported = reported;
+                return Poll::Ready(Some(Err(panic_payload)));
+            }
+        }
+        match timer.as_mut() {
+            Some(timer) => timer.as_mut().poll(cx).map(|_| None),
+            None => Poll::Pending,
+        }
+    })
+    .await;
+    // Cancel the test future before reporting the timeout
+    drop(future);
+    let usage = Usage {
+        elapsed: start.elapsed(),
+        cpu: None,
+        assertion_reported,
+    };
+    match output {
+        Some(result) => Ok((result, usage)),
+        None => Err(TimedOut {
+            usage,
+            exceeded: Exceeded::WallClock,
+            stopped: true,
+            note: String::new(),
+        }),
+    }
+}
+
+fn sleep(duration: Duration) -> impl Future<Output = ()> {
+    thread_timer::Sleep::new(duration)
+}
+
+// Runtime independent timer which parks a helper thread until the deadline is reached. A timer of
+// the runtime would depend on its reactor. With features unified across a workspace it may not be
+// the runtime which polls the test.
+mod thread_timer {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Condvar, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[derive(Default)]
+    struct State {
+        waker: Option<Waker>,
+        fired: bool,
+        dropped: bool,
+    }
+
+    pub struct Sleep {
+        deadline: Instant,
+        state: Option<Arc<(Mutex<State>, Condvar)>>,
+    }
+
+    impl Sleep {
+        pub fn new(duration: Duration) -> Self {
+            Sleep {
+                deadline: Instant::now() + duration,
+                state: None,
+            }
+        }
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if Instant::now() >= self.deadline {
+                return Poll::Ready(());
+            }
+            let deadline = self.deadline;
+            let state = self.state.get_or_insert_with(|| {
+                let state = Arc::new((Mutex::new(State::default()), Condvar::new()));
+                let timer_state = Arc::clone(&state);
+                thread::spawn(move || {
+                    let (lock, condvar) = &*timer_state;
+                    let mut guard = lock.lock().unwrap();
+                    while !guard.dropped && Instant::now() < deadline {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        guard = condvar.wait_timeout(guard, remaining).unwrap().0;
+                    }
+                    guard.fired = true;
+                    if let Some(waker) = guard.waker.take() {
+                        waker.wake();
+                    }
+                });
+                state
+            });
+            let mut guard = state.0.lock().unwrap();
+            if guard.fired {
+                return Poll::Ready(());
+            }
+            guard.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    impl Drop for Sleep {
+        fn drop(&mut self) {
+            if let Some(state) = &self.state {
+                if let Ok(mut guard) = state.0.lock() {
+                    guard.dropped = true;
+                }
+                state.1.notify_one();
+            }
+        }
+    }
+}
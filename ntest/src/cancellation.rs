@@ -0,0 +1,113 @@
+//! Cooperative cancellation of tests which exceeded their timeout.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Token which is cancelled as soon as the test running with the `#[timeout]` attribute exceeds its budget.
+///
+/// A timed out test thread can not be killed. Long running test code or helper libraries should therefore
+/// poll the token and stop their work if it is cancelled. The token can be cloned and moved to other threads.
+///
+/// # Examples
+///
+/// ```
+/// # use ntest::cancellation;
+/// # fn main() {
+/// let token = cancellation();
+/// while !token.is_cancelled() {
+///     // Do some work
+/// #   break;
+/// }
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+// Unwind payload which is used to stop a cancelled test thread
+struct Cancelled;
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+impl CancellationToken {
+    /// Create a new token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the token and all of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Unwinds the current thread if the token was cancelled. The panic hook is not invoked.
+    pub fn check(&self) {
+        if self.is_cancelled() {
+            std::panic::resume_unwind(Box::new(Cancelled));
+        }
+    }
+}
+
+/// Returns the cancellation token of the test running on the current thread.
+///
+/// Outside of a test with the `#[timeout]` attribute a token is returned which is never cancelled.
+pub fn cancellation() -> CancellationToken {
+    CURRENT.with(|current| current.borrow().clone().unwrap_or_default())
+}
+
+/// Stops the current test by unwinding the thread if its timeout was exceeded.
+///
+/// # Examples
+///
+/// ```
+/// # use ntest::check_cancelled;
+/// # fn main() {
+/// for _ in 0..10 {
+///     check_cancelled();
+///     // Do some work
+/// }
+/// # }
+/// ```
+pub fn check_cancelled() {
+    cancellation().check();
+}
+
+pub(crate) fn set_current(token: CancellationToken) {
+    CURRENT.with(|current| *current.borrow_mut() = Some(token));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn not_cancelled_outside_of_timeout() {
+        assert!(!cancellation().is_cancelled());
+        check_cancelled();
+    }
+
+    #[test]
+    fn check_unwinds_if_cancelled() {
+        let token = CancellationToken::new();
+        token.cancel();
+        assert!(std::panic::catch_unwind(|| token.check()).is_err());
+    }
+}
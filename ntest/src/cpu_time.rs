@@ -0,0 +1,86 @@
+//! CPU time of test threads for `#[timeout(cpu = "...")]`.
+//!
+//! On Linux the CPU clock of a thread is the `CLOCK_THREAD_CPUTIME_ID` of that thread. The clock id
+//! returned by `pthread_getcpuclockid` refers to the same clock but can be read from other threads.
+//! On other platforms the CPU time is not measured.
+
+use std::time::Duration;
+
+/// Returns `true` if the CPU time of threads can be measured on this platform.
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// CPU clock of a thread which measures the CPU time since the clock was created.
+#[derive(Clone, Copy, Debug)]
+pub struct ThreadCpuClock {
+    #[cfg(target_os = "linux")]
+    clock: libc::clockid_t,
+    start: Duration,
+}
+
+#[cfg(target_os = "linux")]
+impl ThreadCpuClock {
+    /// Creates the CPU clock of the current thread.
+    pub fn current() -> Option<Self> {
+        let mut clock: libc::clockid_t = 0;
+        if unsafe { libc::pthread_getcpuclockid(libc::pthread_self(), &mut clock) } != 0 {
+            return None;
+        }
+        let mut thread_clock = ThreadCpuClock {
+            clock,
+            start: Duration::from_secs(0),
+        };
+        thread_clock.start = thread_clock.read()?;
+        Some(thread_clock)
+    }
+
+    /// CPU time the thread used since the clock was created. Returns `None` once the thread exited.
+    pub fn elapsed(&self) -> Option<Duration> {
+        Some(self.read()?.saturating_sub(self.start))
+    }
+
+    fn read(&self) -> Option<Duration> {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        if unsafe { libc::clock_gettime(self.clock, &mut time) } != 0 {
+            return None;
+        }
+        Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl ThreadCpuClock {
+    /// Creates the CPU clock of the current thread.
+    pub fn current() -> Option<Self> {
+        None
+    }
+
+    /// CPU time the thread used since the clock was created. Returns `None` once the thread exited.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let _ = self.start;
+        None
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn sleeping_uses_no_cpu_time() {
+        let clock = ThreadCpuClock::current().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(clock.elapsed().unwrap() < Duration::from_millis(25));
+    }
+
+    #[test]
+    fn busy_loop_uses_cpu_time() {
+        let clock = ThreadCpuClock::current().unwrap();
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(50) {}
+        assert!(clock.elapsed().unwrap() >= Duration::from_millis(25));
+    }
+}
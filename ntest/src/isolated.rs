@@ -0,0 +1,284 @@
+//! Runtime support for the `#[isolated]` attribute.
+//!
+//! The parent process re-executes the test binary with an exact filter for the isolated test. The child
+//! process runs the test and writes the outcome to a status file. The parent relays the output of the
+//! child, evaluates the status file together with the exit status, and kills the process group of the
+//! child once it is done or the test is cancelled by a timeout.
+
+use crate::cancellation;
+use crate::timeout::test_name;
+use std::fmt::Debug;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+// Environment variable with the name of the test the child process runs
+const ISOLATED_ENV: &str = "NTEST_ISOLATED";
+// Environment variable with the path of the file the child process writes the test outcome to
+const ISOLATED_STATUS_ENV: &str = "NTEST_ISOLATED_STATUS";
+
+const STATUS_PASSED: &str = "passed";
+const STATUS_PANICKED: &str = "panicked";
+const STATUS_FAILED: &str = "failed";
+
+// Interval in which the parent checks whether the child process exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static STATUS_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Return types which are supported for isolated tests.
+pub trait IsolatedTestOutput: Sized {
+    /// Value which is returned by the parent process if the test passed in the child process.
+    fn success() -> Self;
+    /// Description of the failure if the test returned an error.
+    fn failure(&self) -> Option<String>;
+}
+
+impl IsolatedTestOutput for () {
+    fn success() -> Self {}
+
+    fn failure(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<E: Debug> IsolatedTestOutput for Result<(), E> {
+    fn success() -> Self {
+        Ok(())
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.as_ref().err().map(|e| format!("{:?}", e))
+    }
+}
+
+enum Output {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+}
+
+/// Runs the test in a child process if called in the parent process. The child process runs the test code.
+///
+/// `path` is the module path of the test function including the crate name.
+pub fn execute_isolated<T: IsolatedTestOutput>(path: &str, code: fn() -> T) -> T {
+    let name = test_name(path);
+    if std::env::var(ISOLATED_ENV).as_deref() == Ok(name) {
+        run_child(code)
+    } else {
+        run_parent(name)
+    }
+}
+
+fn run_child<T: IsolatedTestOutput>(code: fn() -> T) -> T {
+    let status_file = std::env::var_os(ISOLATED_STATUS_ENV).map(PathBuf::from);
+    let write_status = |status: &str| {
+        if let Some(status_file) = &status_file {
+            let _ = std::fs::write(status_file, status);
+        }
+    };
+    match std::panic::catch_unwind(code) {
+        Ok(output) => {
+            match output.failure() {
+                Some(failure) => write_status(&format!("{}\n{}", STATUS_FAILED, failure)),
+                None => write_status(STATUS_PASSED),
+            }
+            output
+        }
+        Err(panic_payload) => {
+            write_status(&format!(
+                "{}\n{}",
+                STATUS_PANICKED,
+                panic_message(&*panic_payload)
+            ));
+            std::panic::resume_unwind(panic_payload)
+        }
+    }
+}
+
+#[track_caller]
+fn run_parent<T: IsolatedTestOutput>(name: &str) -> T {
+    let exe = std::env::current_exe().expect("Could not determine path of the test executable");
+    let status_file = std::env::temp_dir().join(format!(
+        "ntest-isolated-{}-{}",
+        std::process::id(),
+        STATUS_FILE_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let mut command = Command::new(&exe);
+    command
+        .args([
+            name,
+            "--exact",
+            "--nocapture",
+            "--include-ignored",
+            "--quiet",
+            "--test-threads=1",
+        ])
+        .env(ISOLATED_ENV, name)
+        .env(ISOLATED_STATUS_ENV, &status_file)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command
+        .spawn()
+        .unwrap_or_else(|e| panic!("Could not start isolated test process {:?}: {}", exe, e));
+
+    let (sender, receiver) = mpsc::channel();
+    let readers = vec![
+        relay(child.stdout.take().unwrap(), sender.clone(), Output::Stdout),
+        relay(child.stderr.take().unwrap(), sender, Output::Stderr),
+    ];
+    let token = cancellation::cancellation();
+    loop {
+        print_output(&receiver);
+        match exited(&mut child) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => panic!("Could not wait for isolated test process: {}", e),
+        }
+        if token.is_cancelled() {
+            kill_process_group(&mut child);
+            let _ = child.wait();
+            let _ = std::fs::remove_file(&status_file);
+            token.check();
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    // Kill processes which were started by the test and are still running. The exited child is
+    // reaped afterwards so that the id of its process group can not be reused in the meantime.
+    kill_process_group(&mut child);
+    let exit_status = child
+        .wait()
+        .unwrap_or_else(|e| panic!("Could not wait for isolated test process: {}", e));
+    for reader in readers {
+        let _ = reader.join();
+    }
+    print_output(&receiver);
+
+    let status = std::fs::read_to_string(&status_file).ok();
+    let _ = std::fs::remove_file(&status_file);
+    let status = match status {
+        Some(status) => status,
+        None => {
+            if exit_status.success() {
+                panic!(
+                    "isolated test '{}' did not run or exited the process before completion",
+                    name
+                );
+            }
+            panic!(
+                "isolated test '{}' terminated abnormally: {}",
+                name,
+                describe_exit_status(&exit_status)
+            );
+        }
+    };
+    let (outcome, message) = status.split_once('\n').unwrap_or((&status, ""));
+    match outcome {
+        STATUS_PASSED if exit_status.success() => T::success(),
+        STATUS_PASSED => panic!(
+            "isolated test '{}' passed but the process terminated abnormally: {}",
+            name,
+            describe_exit_status(&exit_status)
+        ),
+        STATUS_PANICKED => std::panic::resume_unwind(Box::new(message.to_string())),
+        _ => panic!("isolated test '{}' returned an error: {}", name, message),
+    }
+}
+
+fn relay<R: Read + Send + 'static>(
+    mut source: R,
+    sender: mpsc::Sender<Output>,
+    output: fn(Vec<u8>) -> Output,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buffer = [0u8; 4096];
+        loop {
+            match source.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(len) => {
+                    if sender.send(output(buffer[..len].to_vec())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+// Print with the std macros so that the output is captured by the test harness of the parent
+fn print_output(receiver: &mpsc::Receiver<Output>) {
+    for output in receiver.try_iter() {
+        match output {
+            Output::Stdout(bytes) => print!("{}", String::from_utf8_lossy(&bytes)),
+            Output::Stderr(bytes) => eprint!("{}", String::from_utf8_lossy(&bytes)),
+        }
+    }
+}
+
+// Returns `true` once the child exited without reaping it
+#[cfg(unix)]
+fn exited(child: &mut Child) -> std::io::Result<bool> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            child.id() as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // The id is only set if the child changed its state
+    Ok(unsafe { info.si_pid() } != 0)
+}
+
+#[cfg(not(unix))]
+fn exited(child: &mut Child) -> std::io::Result<bool> {
+    child.try_wait().map(|exit_status| exit_status.is_some())
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    // The child is the leader of its own process group and not reaped yet. Killing a group without
+    // running processes fails harmlessly.
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(unix)]
+fn describe_exit_status(exit_status: &ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+    match exit_status.signal() {
+        Some(signal) => format!("killed by signal {}", signal),
+        None => exit_status.to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+fn describe_exit_status(exit_status: &ExitStatus) -> String {
+    exit_status.to_string()
+}
+
+pub(crate) fn panic_message(panic_payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = panic_payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic_payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
@@ -7,9 +7,39 @@ extern crate ntest_timeout;
 #[doc(inline)]
 pub use ntest_test_cases::test_case;
 
+#[doc(inline)]
+pub use ntest_test_cases::test_case_attr;
+
+#[doc(inline)]
+pub use ntest_test_cases::test_case_timeout;
+
+#[doc(inline)]
+pub use ntest_test_cases::test_case_types;
+
+#[doc(inline)]
+pub use ntest_test_cases::test_cases_from;
+
+#[doc(inline)]
+pub use ntest_test_cases::test_files;
+
+#[doc(inline)]
+pub use ntest_test_cases::test_matrix;
+
+#[doc(inline)]
+pub use ntest_test_cases::test_pairwise;
+
 #[doc(inline)]
 pub use ntest_timeout::timeout;
 
+#[doc(inline)]
+pub use ntest_timeout::isolated;
+
+#[doc(inline)]
+pub use ntest_timeout::within;
+
+#[doc(inline)]
+pub use ntest_timeout::retry;
+
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
@@ -19,8 +49,36 @@ mod traits;
 #[doc(inline)]
 pub use crate::traits::MaxDifference;
 
+mod cancellation;
+#[doc(inline)]
+pub use crate::cancellation::{cancellation, check_cancelled, CancellationToken};
+
+mod cpu_time;
+mod report;
+#[doc(hidden)]
+pub use crate::report::{ErrOutput, PlainOutput, TestOutput};
+mod retry;
+mod stack_trace;
+mod timeout;
+#[doc(hidden)]
+pub use crate::timeout::{
+    execute_scoped_test_with_retries, execute_scoped_test_with_timeout, execute_test_with_timeout,
+    execute_within, TimeoutSpec,
+};
+#[doc(inline)]
+pub use crate::timeout::{with_timeout, TimeoutError};
+
+mod isolated;
+#[doc(hidden)]
+pub use crate::isolated::{execute_isolated, IsolatedTestOutput};
+
+mod async_timeout;
+#[doc(hidden)]
+pub use crate::async_timeout::{execute_future_with_retries, execute_future_with_timeout};
+
 #[doc(hidden)]
 /// Timeout helper for proc macro timeout
+#[allow(clippy::manual_ok_err)]
 pub fn execute_with_timeout<T: Send>(
     code: &'static (dyn Fn() -> T + Sync + 'static),
     timeout_ms: u64,
@@ -33,6 +91,14 @@ pub fn execute_with_timeout<T: Send>(
     }
 }
 
+#[doc(hidden)]
+/// Failure helper for the assertion macros which writes a record to the `NTEST_REPORT` file
+#[track_caller]
+pub fn assertion_failed(source: &str, message: String) -> ! {
+    report::write_assertion_failure(source, &message);
+    panic!("{}", message)
+}
+
 #[doc(hidden)]
 /// Difference helper for proc macro about equal
 pub fn about_eq<T: MaxDifference>(a: T, b: T, eps: f64) -> bool {
@@ -81,14 +147,16 @@ pub fn about_eq<T: MaxDifference>(a: T, b: T, eps: f64) -> bool {
 macro_rules! assert_about_eq {
     ($a:expr, $b:expr, $eps:expr) => {
         let eps = $eps;
-        assert!(
-            $crate::about_eq($a, $b, eps),
-            "assertion failed: `(left !== right)` \
-             (left: `{:?}`, right: `{:?}`, epsilon: `{:?}`)",
-            $a,
-            $b,
-            eps
-        );
+        if !$crate::about_eq($a, $b, eps) {
+            $crate::assertion_failed(
+                "assert_about_eq",
+                format!(
+                    "assertion failed: `(left !== right)` \
+                     (left: `{:?}`, right: `{:?}`, epsilon: `{:?}`)",
+                    $a, $b, eps
+                ),
+            );
+        }
     };
     ($a:expr, $b:expr,$eps:expr,) => {
         assert_about_eq!($a, $b, $eps);
@@ -126,7 +194,10 @@ macro_rules! assert_about_eq {
 macro_rules! assert_true {
     ($x:expr) => {
         if !$x {
-            panic!("assertion failed: Expected 'true', but was 'false'");
+            $crate::assertion_failed(
+                "assert_true",
+                "assertion failed: Expected 'true', but was 'false'".to_string(),
+            );
         }
     };
     ($x:expr,) => {
@@ -157,7 +228,10 @@ macro_rules! assert_true {
 macro_rules! assert_false {
     ($x:expr) => {{
         if $x {
-            panic!("assertion failed: Expected 'false', but was 'true'");
+            $crate::assertion_failed(
+                "assert_false",
+                "assertion failed: Expected 'false', but was 'true'".to_string(),
+            );
         }
     }};
     ($x:expr,) => {{
@@ -193,7 +267,10 @@ macro_rules! assert_panics {
     ($x:block) => {{
         let result = std::panic::catch_unwind(|| $x);
         if !result.is_err() {
-            panic!("assertion failed: code in block did not panic");
+            $crate::assertion_failed(
+                "assert_panics",
+                "assertion failed: code in block did not panic".to_string(),
+            );
         }
     }};
     ($x:block,) => {{
@@ -203,7 +280,6 @@ macro_rules! assert_panics {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
     #[test]
     fn assert_true() {
         assert_true!(true);
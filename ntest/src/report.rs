@@ -0,0 +1,254 @@
+//! Machine readable records of test outcomes.
+//!
+//! If the `NTEST_REPORT` environment variable is set to a path, the `#[timeout]` wrapper and the
+//! assertion macros append one JSON object per line to that file. The file is opened in append mode
+//! for every record so that tests running in parallel threads or processes can share it.
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Environment variable with the path of the JSON-lines report file
+const REPORT_ENV: &str = "NTEST_REPORT";
+
+// Serializes the writes of parallel test threads
+static REPORT_FILE: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    // Set by a failed assertion macro. The `#[timeout]` wrapper then does not write a second
+    // failure record for the panic of the assertion.
+    static ASSERTION_REPORTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Outcome of a test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Pass,
+    Fail,
+    Timeout,
+}
+
+impl Outcome {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "fail",
+            Outcome::Timeout => "timeout",
+        }
+    }
+}
+
+/// Output of a test function. The generated code calls `(&TestOutput(&output)).ntest_failure()`
+/// which selects [ErrOutput] for a `Result` and [PlainOutput] for every other type.
+pub struct TestOutput<'a, T>(pub &'a T);
+
+/// Failure of a test function which returned a `Result`.
+pub trait ErrOutput {
+    /// Description of the error like libtest prints it, or `None` for `Ok`.
+    fn ntest_failure(&self) -> Option<String>;
+}
+
+impl<T, E: Debug> ErrOutput for TestOutput<'_, Result<T, E>> {
+    fn ntest_failure(&self) -> Option<String> {
+        self.0.as_ref().err().map(|e| format!("Error: {:?}", e))
+    }
+}
+
+/// Output of a test function which can not fail.
+pub trait PlainOutput {
+    /// Always `None`.
+    fn ntest_failure(&self) -> Option<String>;
+}
+
+impl<T> PlainOutput for &TestOutput<'_, T> {
+    fn ntest_failure(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A single line of the report.
+#[derive(Debug)]
+pub(crate) struct Record<'a> {
+    /// Name of the test as used by libtest.
+    pub name: &'a str,
+    /// Component which wrote the record. For example `timeout` or `assert_true`.
+    pub source: &'a str,
+    pub outcome: Outcome,
+    pub elapsed: Option<Duration>,
+    pub budget: Option<Duration>,
+    pub cpu: Option<Duration>,
+    pub cpu_budget: Option<Duration>,
+    /// Number of the run for tests with retries starting at `1`.
+    pub attempt: Option<u32>,
+    pub message: Option<&'a str>,
+}
+
+impl Record<'_> {
+    fn to_json(&self) -> String {
+        let mut json = String::new();
+        json.push_str("{\"name\":");
+        push_json_string(&mut json, self.name);
+        json.push_str(",\"source\":");
+        push_json_string(&mut json, self.source);
+        json.push_str(",\"outcome\":");
+        push_json_string(&mut json, self.outcome.as_str());
+        json.push_str(",\"elapsed_ms\":");
+        push_json_millis(&mut json, self.elapsed);
+        json.push_str(",\"budget_ms\":");
+        push_json_millis(&mut json, self.budget);
+        json.push_str(",\"cpu_ms\":");
+        push_json_millis(&mut json, self.cpu);
+        json.push_str(",\"cpu_budget_ms\":");
+        push_json_millis(&mut json, self.cpu_budget);
+        json.push_str(",\"attempt\":");
+        match self.attempt {
+            Some(attempt) => {
+                let _ = write!(json, "{}", attempt);
+            }
+            None => json.push_str("null"),
+        }
+        json.push_str(",\"message\":");
+        match self.message {
+            Some(message) => push_json_string(&mut json, message),
+            None => json.push_str("null"),
+        }
+        json.push('}');
+        json
+    }
+}
+
+fn push_json_millis(json: &mut String, duration: Option<Duration>) {
+    match duration {
+        Some(duration) => {
+            let _ = write!(json, "{}", duration.as_secs_f64() * 1000.0);
+        }
+        None => json.push_str("null"),
+    }
+}
+
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Appends the record to the report file if one is configured.
+pub(crate) fn write(record: &Record) {
+    let path = match std::env::var_os(REPORT_ENV) {
+        Some(path) if !path.is_empty() => path,
+        _ => return,
+    };
+    let line = format!("{}\n", record.to_json());
+    let _guard = REPORT_FILE.lock();
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        let _ = writeln!(
+            std::io::stderr(),
+            "[ntest] warning: could not write to {} {:?}: {}",
+            REPORT_ENV,
+            path,
+            e
+        );
+    }
+}
+
+/// Appends a failure record of an assertion macro. The test name is taken from the current thread
+/// which libtest names after the test.
+pub(crate) fn write_assertion_failure(source: &str, message: &str) {
+    write(&Record {
+        name: std::thread::current().name().unwrap_or("<unknown>"),
+        source,
+        outcome: Outcome::Fail,
+        elapsed: None,
+        budget: None,
+        cpu: None,
+        cpu_budget: None,
+        attempt: None,
+        message: Some(message),
+    });
+    ASSERTION_REPORTED.with(|reported| reported.set(true));
+}
+
+/// Runs the test code on the current thread and catches its panic. Returns `true` as well if the
+/// panic comes from an assertion macro which already wrote the failure record.
+pub(crate) fn catch_test_panic<T>(code: impl FnOnce() -> T) -> (std::thread::Result<T>, bool) {
+    ASSERTION_REPORTED.with(|reported| reported.set(false));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(code));
+    let reported = result.is_err() && ASSERTION_REPORTED.with(|reported| reported.replace(false));
+    (result, reported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_to_json() {
+        let record = Record {
+            name: "tests::slow",
+            source: "timeout",
+            outcome: Outcome::Timeout,
+            elapsed: Some(Duration::from_millis(12)),
+            budget: Some(Duration::from_millis(10)),
+            cpu: Some(Duration::from_micros(1500)),
+            cpu_budget: None,
+            attempt: Some(2),
+            message: Some("took \"too\" long\n\u{1}"),
+        };
+        assert_eq!(
+            record.to_json(),
+            r#"{"name":"tests::slow","source":"timeout","outcome":"timeout","elapsed_ms":12,"budget_ms":10,"cpu_ms":1.5,"cpu_budget_ms":null,"attempt":2,"message":"took \"too\" long\n\u0001"}"#
+        );
+    }
+
+    #[test]
+    fn error_output_is_a_failure() {
+        let error: Result<(), &str> = Err("broken");
+        assert_eq!(
+            TestOutput(&error).ntest_failure(),
+            Some("Error: \"broken\"".to_string())
+        );
+        let ok: Result<u32, &str> = Ok(1);
+        assert_eq!(TestOutput(&ok).ntest_failure(), None);
+        assert_eq!((&TestOutput(&42)).ntest_failure(), None);
+    }
+
+    #[test]
+    fn record_without_measurements() {
+        let record = Record {
+            name: "tests::assertion",
+            source: "assert_true",
+            outcome: Outcome::Fail,
+            elapsed: None,
+            budget: None,
+            cpu: None,
+            cpu_budget: None,
+            attempt: None,
+            message: None,
+        };
+        assert_eq!(
+            record.to_json(),
+            r#"{"name":"tests::assertion","source":"assert_true","outcome":"fail","elapsed_ms":null,"budget_ms":null,"cpu_ms":null,"cpu_budget_ms":null,"attempt":null,"message":null}"#
+        );
+    }
+}
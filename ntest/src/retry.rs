@@ -0,0 +1,261 @@
+//! Retries of failing or timed out tests for `#[timeout(.., retries = n)]` and `#[retry(n)]`.
+//!
+//! Every attempt is reported on its own. If an attempt passes after earlier attempts failed, a
+//! warning with the outcome of every attempt is printed. If all attempts fail, the test panics with
+//! the outcome of every attempt.
+
+use crate::isolated::panic_message;
+use crate::report::Outcome;
+use crate::timeout::{Exceeded, Thresholds, TimeoutSpec, Usage};
+use std::io::Write;
+use std::panic;
+use std::thread;
+use std::time::Duration;
+
+// Environment variable which disables all retries if set to `0`, `off`, `false`, or `disabled`
+const RETRIES_ENV: &str = "NTEST_RETRIES";
+
+/// Returns `false` if retries are switched off via the `NTEST_RETRIES` environment variable.
+pub fn retries_enabled() -> bool {
+    match std::env::var(RETRIES_ENV) {
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "off" | "false" | "0" | "disabled" | "disable" => false,
+            "" | "on" | "true" | "1" | "enabled" | "enable" => true,
+            _ => panic!(
+                "Invalid value '{}' for {}. Expected 'on' or 'off'",
+                value, RETRIES_ENV
+            ),
+        },
+        Err(_) => true,
+    }
+}
+
+/// Outcome of a single run of a test. `Err` if the test exceeded its budget.
+pub(crate) type Attempt<T> = Result<(thread::Result<T>, Usage), TimedOut>;
+
+/// Test thread or future which exceeded its budget.
+pub(crate) struct TimedOut {
+    pub usage: Usage,
+    pub exceeded: Exceeded,
+    /// `true` if the test stopped within the grace period after the cancellation.
+    pub stopped: bool,
+    pub note: String,
+}
+
+/// Attempts of a test. Tests without retries have a single attempt.
+pub(crate) struct Attempts<'a> {
+    spec: &'a TimeoutSpec,
+    thresholds: Option<Thresholds>,
+    total: u32,
+    current: u32,
+    failures: Vec<String>,
+}
+
+impl<'a> Attempts<'a> {
+    pub(crate) fn new(spec: &'a TimeoutSpec) -> Self {
+        let retries = if retries_enabled() { spec.retries } else { 0 };
+        Attempts {
+            spec,
+            thresholds: spec.thresholds(),
+            total: retries.saturating_add(1),
+            current: 1,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Thresholds of an attempt which must be stopped once it exceeds its budget.
+    pub(crate) fn budget(&self) -> Option<&Thresholds> {
+        self.thresholds
+            .as_ref()
+            .filter(|thresholds| thresholds.fail.is_some())
+    }
+
+    /// Describes the timeout of the current attempt and reports it.
+    pub(crate) fn describe_timeout(&self, timed_out: &TimedOut) -> String {
+        let thresholds = self
+            .budget()
+            .expect("only attempts with a budget can time out");
+        let message = self.spec.timeout_message(
+            thresholds,
+            &timed_out.usage,
+            timed_out.exceeded,
+            &timed_out.note,
+        );
+        self.spec.report(
+            Outcome::Timeout,
+            &timed_out.usage,
+            Some(thresholds),
+            self.current,
+            Some(&message),
+        );
+        message
+    }
+
+    /// Reports the attempt. Returns the output of a passed attempt, or `None` if the test should be
+    /// run again. Panics once the last attempt failed. `failure` describes an output such as
+    /// `Result::Err` which fails the test. Such an output of the last attempt is returned.
+    #[track_caller]
+    pub(crate) fn evaluate<T>(
+        &mut self,
+        attempt: Attempt<T>,
+        failure: impl Fn(&T) -> Option<String>,
+    ) -> Option<T> {
+        let (outcome, elapsed, message) = match attempt {
+            Ok((result, usage)) => {
+                if let Some(thresholds) = &self.thresholds {
+                    self.spec.warn_if_slow(thresholds, usage.elapsed);
+                }
+                match result {
+                    Ok(output) => match failure(&output) {
+                        None => {
+                            self.spec.report(
+                                Outcome::Pass,
+                                &usage,
+                                self.thresholds.as_ref(),
+                                self.current,
+                                None,
+                            );
+                            self.warn_if_flaky();
+                            return Some(output);
+                        }
+                        Some(message) => {
+                            self.spec.report(
+                                Outcome::Fail,
+                                &usage,
+                                self.thresholds.as_ref(),
+                                self.current,
+                                Some(&message),
+                            );
+                            if self.total == 1 {
+                                return Some(output);
+                            }
+                            (Outcome::Fail, usage.elapsed, message)
+                        }
+                    },
+                    Err(panic_payload) => {
+                        let message = panic_message(&*panic_payload);
+                        // A failed assertion macro already wrote the record of the test
+                        if !usage.assertion_reported {
+                            self.spec.report(
+                                Outcome::Fail,
+                                &usage,
+                                self.thresholds.as_ref(),
+                                self.current,
+                                Some(&message),
+                            );
+                        }
+                        if self.total == 1 {
+                            panic::resume_unwind(panic_payload);
+                        }
+                        (Outcome::Fail, usage.elapsed, message)
+                    }
+                }
+            }
+            Err(timed_out) => {
+                let message = self.describe_timeout(&timed_out);
+                if self.total == 1 {
+                    panic!("{}", message);
+                }
+                (Outcome::Timeout, timed_out.usage.elapsed, message)
+            }
+        };
+        self.failures
+            .push(self.describe_attempt(outcome, elapsed, &message));
+        if self.current == self.total {
+            panic!(
+                "test failed in all {} attempts:\n{}",
+                self.total,
+                self.failures.join("\n")
+            );
+        }
+        self.current += 1;
+        None
+    }
+
+    fn describe_attempt(&self, outcome: Outcome, elapsed: Duration, message: &str) -> String {
+        format!(
+            "attempt {}/{}: {} after {} ms: {}",
+            self.current,
+            self.total,
+            outcome.as_str(),
+            elapsed.as_millis(),
+            message
+        )
+    }
+
+    // Prints a warning which bypasses the output capturing of libtest if earlier attempts failed
+    fn warn_if_flaky(&self) {
+        if self.failures.is_empty() {
+            return;
+        }
+        let _ = writeln!(
+            std::io::stderr(),
+            "[ntest] warning: flaky test name=\"{}\" passed in attempt {}/{}\n[ntest] {}",
+            self.spec.name(),
+            self.current,
+            self.total,
+            self.failures.join("\n[ntest] ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::{ErrOutput, TestOutput};
+    use crate::timeout::{execute_test_with_timeout, TimeoutSpec};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    fn spec(fail: Option<Duration>, retries: u32) -> TimeoutSpec {
+        TimeoutSpec {
+            path: "ntest::retry::tests",
+            fail,
+            retries,
+            ..TimeoutSpec::default()
+        }
+    }
+
+    #[test]
+    fn passes_in_second_attempt() {
+        static RUNS: AtomicU32 = AtomicU32::new(0);
+        let runs = execute_test_with_timeout(
+            spec(Some(Duration::from_millis(50)), 2),
+            || {
+                let runs = RUNS.fetch_add(1, Ordering::SeqCst) + 1;
+                if runs == 1 {
+                    thread::sleep(Duration::from_millis(500));
+                }
+                runs
+            },
+            |_| None,
+        );
+        assert_eq!(runs, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "test failed in all 3 attempts:\nattempt 1/3: fail after")]
+    fn fails_in_all_attempts() {
+        execute_test_with_timeout(spec(None, 2), || panic!("I am panicing"), |_| None);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt 2/2: fail after 0 ms: Error: \"broken\"")]
+    fn error_output_fails_attempt() {
+        let _ = execute_test_with_timeout(
+            spec(None, 1),
+            || Err::<(), _>("broken"),
+            |output| TestOutput(output).ntest_failure(),
+        );
+    }
+
+    #[test]
+    fn error_output_of_single_attempt_is_returned() {
+        let output = execute_test_with_timeout(
+            spec(None, 0),
+            || Err::<(), _>("broken"),
+            |output| TestOutput(output).ntest_failure(),
+        );
+        assert_eq!(output, Err("broken"));
+    }
+}
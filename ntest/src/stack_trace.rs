@@ -0,0 +1,471 @@
+//! Stack traces of test threads which exceeded their timeout.
+//!
+//! The stack traces are opt-in via `NTEST_TIMEOUT_BACKTRACE=1`. On Linux a signal is sent to the
+//! hung test thread only. `NTEST_TIMEOUT_BACKTRACE=all` captures every other thread of the process
+//! afterwards, one at a time, so both sides of a deadlock are shown. The signal handler neither
+//! allocates nor takes locks of this crate. It walks the stack and stores the return addresses in a
+//! preallocated buffer. The addresses are symbolized afterwards by the monitoring thread. Unwinding
+//! may still wait for a lock of the dynamic loader, so the stack trace is skipped if the handler
+//! does not answer in time.
+//!
+//! The handler is only installed for `SIGRTMIN+2` while a stack trace is captured. A handler which
+//! the program installed before is called for signals which were not sent by ntest and is restored
+//! afterwards. If a thread does not answer in time, the handler of ntest stays installed since the
+//! signal may still be pending. It keeps forwarding other signals to the previous handler.
+
+// Environment variable which enables the stack traces on timeout
+const BACKTRACE_ENV: &str = "NTEST_TIMEOUT_BACKTRACE";
+
+// Threads whose stack traces are added to the timeout message
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Off,
+    TestThread,
+    AllThreads,
+}
+
+fn mode() -> Mode {
+    match std::env::var(BACKTRACE_ENV) {
+        Ok(value) => match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "on" | "true" => Mode::TestThread,
+            "all" => Mode::AllThreads,
+            _ => Mode::Off,
+        },
+        Err(_) => Mode::Off,
+    }
+}
+
+/// Returns the id of the current thread which is used to capture its stack trace later on.
+#[cfg(target_os = "linux")]
+pub fn current_thread_id() -> i32 {
+    linux::gettid()
+}
+
+/// Returns the id of the current thread which is used to capture its stack trace later on.
+#[cfg(not(target_os = "linux"))]
+pub fn current_thread_id() -> i32 {
+    0
+}
+
+/// Describes the stack trace of the test thread, or of all threads, if `NTEST_TIMEOUT_BACKTRACE`
+/// is enabled. Returns an empty string if no stack trace could be captured.
+#[cfg(target_os = "linux")]
+pub fn describe(test_thread: i32) -> String {
+    match mode() {
+        _ if test_thread == 0 => String::new(),
+        Mode::Off => String::new(),
+        Mode::TestThread => describe_thread(test_thread),
+        Mode::AllThreads => describe_all_threads(test_thread),
+    }
+}
+
+/// Describes the stack trace of the test thread, or of all threads, if `NTEST_TIMEOUT_BACKTRACE`
+/// is enabled. Returns an empty string if no stack trace could be captured.
+#[cfg(not(target_os = "linux"))]
+pub fn describe(_test_thread: i32) -> String {
+    let _ = mode();
+    String::new()
+}
+
+#[cfg(target_os = "linux")]
+fn describe_thread(thread_id: i32) -> String {
+    describe_stack(thread_id, "the test thread")
+}
+
+// The monitoring thread which calls this function is skipped
+#[cfg(target_os = "linux")]
+fn describe_all_threads(test_thread: i32) -> String {
+    let mut description = describe_thread(test_thread);
+    let current = linux::gettid();
+    for thread_id in linux::thread_ids() {
+        if thread_id != test_thread && thread_id != current {
+            let title = format!("thread {} \"{}\"", thread_id, linux::thread_name(thread_id));
+            description.push_str(&describe_stack(thread_id, &title));
+        }
+    }
+    description
+}
+
+#[cfg(target_os = "linux")]
+fn describe_stack(thread_id: i32, title: &str) -> String {
+    match linux::capture(thread_id) {
+        Some(addresses) if !addresses.is_empty() => {
+            let mut description = format!("\n\nstack backtrace of {}:", title);
+            for (index, frame) in linux::symbolize(&addresses).iter().enumerate() {
+                description.push_str(&format!("\n{:>4}: {}", index, frame));
+            }
+            description
+        }
+        _ => String::new(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::BTreeMap;
+    use std::ffi::CStr;
+    use std::process::{Command, Stdio};
+    use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    // Time the signal handler gets to capture the stack trace
+    const CAPTURE_TIMEOUT: Duration = Duration::from_secs(1);
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+    const MAX_FRAMES: usize = 128;
+
+    // States of the capture which is shared with the signal handler
+    const IDLE: u8 = 0;
+    const REQUESTED: u8 = 1;
+    const CAPTURING: u8 = 2;
+    const CAPTURED: u8 = 3;
+
+    // Only one capture at a time since the handler writes to a global buffer. Holds the action
+    // which was installed before while the handler of ntest is installed.
+    static CAPTURE: Mutex<Option<libc::sigaction>> = Mutex::new(None);
+    // Handler of the previous action which is called for signals which were not sent by ntest
+    static PREVIOUS_HANDLER: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+    static PREVIOUS_SIGINFO: AtomicBool = AtomicBool::new(false);
+    static STATE: AtomicU8 = AtomicU8::new(IDLE);
+    static TARGET: AtomicI32 = AtomicI32::new(0);
+    static FRAME_COUNT: AtomicUsize = AtomicUsize::new(0);
+    static FRAMES: [AtomicUsize; MAX_FRAMES] = [const { AtomicUsize::new(0) }; MAX_FRAMES];
+
+    #[repr(C)]
+    struct UnwindContext {
+        _private: [u8; 0],
+    }
+
+    const URC_NO_REASON: libc::c_int = 0;
+    const URC_END_OF_STACK: libc::c_int = 5;
+
+    extern "C" {
+        fn _Unwind_Backtrace(
+            trace: extern "C" fn(*mut UnwindContext, *mut libc::c_void) -> libc::c_int,
+            data: *mut libc::c_void,
+        ) -> libc::c_int;
+        fn _Unwind_GetIP(context: *mut UnwindContext) -> usize;
+    }
+
+    pub fn signal() -> libc::c_int {
+        libc::SIGRTMIN() + 2
+    }
+
+    // Only uses atomics and the unwinder since it runs in a signal handler
+    extern "C" fn handler(
+        signal: libc::c_int,
+        info: *mut libc::siginfo_t,
+        context: *mut libc::c_void,
+    ) {
+        if gettid() != TARGET.load(Ordering::SeqCst)
+            || STATE
+                .compare_exchange(REQUESTED, CAPTURING, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+        {
+            forward(signal, info, context);
+            return;
+        }
+        unsafe {
+            _Unwind_Backtrace(record_frame, std::ptr::null_mut());
+        }
+        STATE.store(CAPTURED, Ordering::SeqCst);
+    }
+
+    extern "C" fn record_frame(context: *mut UnwindContext, _: *mut libc::c_void) -> libc::c_int {
+        let index = FRAME_COUNT.load(Ordering::SeqCst);
+        if index == MAX_FRAMES {
+            return URC_END_OF_STACK;
+        }
+        FRAMES[index].store(unsafe { _Unwind_GetIP(context) }, Ordering::SeqCst);
+        FRAME_COUNT.store(index + 1, Ordering::SeqCst);
+        URC_NO_REASON
+    }
+
+    // Calls the previous handler. The default action of a real-time signal would terminate the
+    // process, so signals without a previous handler are ignored.
+    fn forward(signal: libc::c_int, info: *mut libc::siginfo_t, context: *mut libc::c_void) {
+        let previous = PREVIOUS_HANDLER.load(Ordering::SeqCst);
+        if previous == libc::SIG_DFL || previous == libc::SIG_IGN {
+            return;
+        }
+        unsafe {
+            if PREVIOUS_SIGINFO.load(Ordering::SeqCst) {
+                let previous: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                    std::mem::transmute(previous);
+                previous(signal, info, context);
+            } else {
+                let previous: extern "C" fn(libc::c_int) = std::mem::transmute(previous);
+                previous(signal);
+            }
+        }
+    }
+
+    // Installs the handler and saves the previous action unless the handler is still installed
+    fn install_handler(previous: &mut Option<libc::sigaction>) {
+        if previous.is_some() {
+            return;
+        }
+        unsafe {
+            let mut current: libc::sigaction = std::mem::zeroed();
+            libc::sigaction(signal(), std::ptr::null(), &mut current);
+            PREVIOUS_HANDLER.store(current.sa_sigaction, Ordering::SeqCst);
+            PREVIOUS_SIGINFO.store(current.sa_flags & libc::SA_SIGINFO != 0, Ordering::SeqCst);
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal(), &action, &mut current);
+            *previous = Some(current);
+        }
+    }
+
+    fn restore_handler(previous: &mut Option<libc::sigaction>) {
+        if let Some(action) = previous.take() {
+            unsafe {
+                libc::sigaction(signal(), &action, std::ptr::null_mut());
+            }
+        }
+    }
+
+    pub fn gettid() -> i32 {
+        unsafe { libc::syscall(libc::SYS_gettid) as i32 }
+    }
+
+    /// Interrupts the thread and returns the return addresses of its stack.
+    pub fn capture(thread_id: i32) -> Option<Vec<usize>> {
+        let mut previous = CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+        // A handler which did not answer in time may still write to the buffer
+        match STATE.load(Ordering::SeqCst) {
+            REQUESTED | CAPTURING => return None,
+            _ => {}
+        }
+        install_handler(&mut previous);
+        FRAME_COUNT.store(0, Ordering::SeqCst);
+        TARGET.store(thread_id, Ordering::SeqCst);
+        STATE.store(REQUESTED, Ordering::SeqCst);
+        let pid = unsafe { libc::getpid() };
+        if unsafe { libc::syscall(libc::SYS_tgkill, pid, thread_id, signal()) } != 0 {
+            STATE.store(IDLE, Ordering::SeqCst);
+            restore_handler(&mut previous);
+            return None;
+        }
+        let start = Instant::now();
+        while STATE.load(Ordering::SeqCst) != CAPTURED {
+            if start.elapsed() >= CAPTURE_TIMEOUT {
+                // The signal may still be pending. The handler stays installed to not deliver it
+                // to the default action which would terminate the process.
+                let _ = STATE.compare_exchange(REQUESTED, IDLE, Ordering::SeqCst, Ordering::SeqCst);
+                return None;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        restore_handler(&mut previous);
+        let addresses = FRAMES[..FRAME_COUNT.load(Ordering::SeqCst)]
+            .iter()
+            .map(|frame| frame.load(Ordering::SeqCst))
+            // Skip the frame of the signal handler
+            .skip(1)
+            .filter(|address| *address != 0)
+            .collect();
+        STATE.store(IDLE, Ordering::SeqCst);
+        Some(addresses)
+    }
+
+    /// Ids of all threads of the process in ascending order.
+    pub fn thread_ids() -> Vec<i32> {
+        let mut thread_ids: Vec<i32> = std::fs::read_dir("/proc/self/task")
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        thread_ids.sort_unstable();
+        thread_ids
+    }
+
+    pub fn thread_name(thread_id: i32) -> String {
+        std::fs::read_to_string(format!("/proc/self/task/{}/comm", thread_id))
+            .map(|name| name.trim_end().to_string())
+            .unwrap_or_default()
+    }
+
+    struct Location {
+        file: String,
+        offset: usize,
+        symbol: Option<String>,
+    }
+
+    fn locate(address: usize) -> Option<Location> {
+        let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+        if unsafe { libc::dladdr(address as *const libc::c_void, &mut info) } == 0
+            || info.dli_fname.is_null()
+        {
+            return None;
+        }
+        let symbol = if info.dli_sname.is_null() {
+            None
+        } else {
+            Some(
+                unsafe { CStr::from_ptr(info.dli_sname) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+        Some(Location {
+            file: unsafe { CStr::from_ptr(info.dli_fname) }
+                .to_string_lossy()
+                .into_owned(),
+            offset: address - info.dli_fbase as usize,
+            symbol,
+        })
+    }
+
+    /// Describes the return addresses with `addr2line` if it is installed. Otherwise the dynamic
+    /// symbol and the offset within the object file are described.
+    pub fn symbolize(addresses: &[usize]) -> Vec<String> {
+        // Return addresses point behind the call
+        let locations: Vec<Option<Location>> = addresses
+            .iter()
+            .map(|address| locate(address.saturating_sub(1)))
+            .collect();
+        let mut files: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (index, location) in locations.iter().enumerate() {
+            if let Some(location) = location {
+                files.entry(&location.file).or_default().push(index);
+            }
+        }
+        let mut frames: Vec<Option<String>> = vec![None; addresses.len()];
+        for (file, indices) in files {
+            let offsets = indices
+                .iter()
+                .map(|index| format!("{:#x}", locations[*index].as_ref().map_or(0, |l| l.offset)));
+            let lines = addr2line(file, offsets).unwrap_or_default();
+            if lines.len() != indices.len() {
+                continue;
+            }
+            for (index, line) in indices.into_iter().zip(lines) {
+                if !line.starts_with("??") {
+                    frames[index] = Some(line);
+                }
+            }
+        }
+        frames
+            .into_iter()
+            .zip(addresses.iter().zip(locations))
+            .map(|(frame, (address, location))| {
+                frame.unwrap_or_else(|| match location {
+                    Some(location) => format!(
+                        "{} ({}+{:#x})",
+                        location.symbol.as_deref().unwrap_or("<unknown>"),
+                        location.file,
+                        location.offset
+                    ),
+                    None => format!("<unknown> ({:#x})", address),
+                })
+            })
+            .collect()
+    }
+
+    fn addr2line(file: &str, offsets: impl Iterator<Item = String>) -> Option<Vec<String>> {
+        let output = Command::new("addr2line")
+            .args(["-f", "-C", "-p", "-e", file])
+            .args(offsets)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::sync::{mpsc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    // The tests change the process wide handler of the signal
+    static SIGNAL: Mutex<()> = Mutex::new(());
+
+    // Spawns a thread which waits until the returned sender is dropped
+    fn spawn_waiting(name: &str) -> (thread::JoinHandle<()>, i32, mpsc::Sender<()>) {
+        let (sender, receiver) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let waiting = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                sender.send(current_thread_id()).unwrap();
+                let _ = stopped.recv();
+            })
+            .unwrap();
+        let thread_id = receiver.recv().unwrap();
+        (waiting, thread_id, stop)
+    }
+
+    #[test]
+    fn describes_the_stack_of_a_sleeping_thread() {
+        let _signal = SIGNAL.lock().unwrap_or_else(|e| e.into_inner());
+        let (sender, receiver) = mpsc::channel();
+        let sleeper = thread::spawn(move || {
+            sender.send(current_thread_id()).unwrap();
+            thread::sleep(Duration::from_millis(300));
+        });
+        let thread_id = receiver.recv().unwrap();
+        thread::sleep(Duration::from_millis(50));
+        let description = describe_thread(thread_id);
+        sleeper.join().unwrap();
+        assert!(description.starts_with("\n\nstack backtrace of the test thread:"));
+        assert!(description.contains("sleep"), "{}", description);
+    }
+
+    #[test]
+    fn describes_all_threads() {
+        let _signal = SIGNAL.lock().unwrap_or_else(|e| e.into_inner());
+        let (test_thread, test_thread_id, stop_test_thread) = spawn_waiting("ntest-test");
+        let (other, _, stop_other) = spawn_waiting("ntest-other");
+        let description = describe_all_threads(test_thread_id);
+        drop((stop_test_thread, stop_other));
+        test_thread.join().unwrap();
+        other.join().unwrap();
+        assert!(description.starts_with("\n\nstack backtrace of the test thread:"));
+        assert!(description.contains("\"ntest-other\":"), "{}", description);
+    }
+
+    extern "C" fn previous_handler(_: libc::c_int) {}
+
+    #[test]
+    fn restores_the_previous_handler() {
+        let _signal = SIGNAL.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = previous_handler as *const () as libc::sighandler_t;
+        unsafe { libc::signal(linux::signal(), previous) };
+        let (waiting, thread_id, stop) = spawn_waiting("ntest-waiting");
+        let description = describe_thread(thread_id);
+        drop(stop);
+        waiting.join().unwrap();
+        let mut current: libc::sigaction = unsafe { std::mem::zeroed() };
+        unsafe {
+            libc::sigaction(linux::signal(), std::ptr::null(), &mut current);
+            libc::signal(linux::signal(), libc::SIG_DFL);
+        }
+        assert!(!description.is_empty());
+        assert_eq!(current.sa_sigaction, previous);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        if std::env::var_os(BACKTRACE_ENV).is_none() {
+            assert_eq!(describe(current_thread_id()), "");
+        }
+    }
+}
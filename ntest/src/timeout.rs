@@ -0,0 +1,757 @@
+//! Runtime support for the `#[timeout]` attribute.
+
+use crate::cancellation::{self, CancellationToken};
+use crate::cpu_time::{self, ThreadCpuClock};
+use crate::report::{self, Outcome, Record};
+use crate::retry::{Attempt, Attempts, TimedOut};
+use crate::stack_trace;
+use std::io::Write;
+use std::panic;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Environment variable which disables all timeouts if set to `off`, `false`, `0`, or `disabled`
+const TIMEOUT_ENV: &str = "NTEST_TIMEOUT";
+// Environment variable with a factor which is applied to all timeouts. For example `3` or `0.5`
+const TIMEOUT_SCALE_ENV: &str = "NTEST_TIMEOUT_SCALE";
+
+// Environment variables used by libtest for the `--report-time` thresholds
+const TEST_TIME_UNIT_ENV: &str = "RUST_TEST_TIME_UNIT";
+const TEST_TIME_INTEGRATION_ENV: &str = "RUST_TEST_TIME_INTEGRATION";
+
+// Time a cancelled test thread gets to unwind before the timeout is reported
+const CANCELLATION_GRACE_PERIOD: Duration = Duration::from_millis(100);
+// End of the note of a detached test thread which ignored the cancellation
+const KEEPS_RUNNING: &str = " and keeps running";
+
+// Interval in which the runtime checks for an attached debugger while waiting for a test
+const TRACER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Interval in which the CPU time of a test with a CPU time limit is checked
+const CPU_POLL_INTERVAL: Duration = Duration::from_millis(10);
+// Factor of the CPU time limit which is used as wall-clock limit if no explicit one is given
+const CPU_BACKSTOP_FACTOR: u32 = 10;
+
+/// Returns `false` if timeouts are switched off via the `NTEST_TIMEOUT` environment variable.
+pub fn timeouts_enabled() -> bool {
+    env_value(TIMEOUT_ENV, "'on' or 'off'", |value| {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" | "false" | "0" | "disabled" | "disable" => Some(false),
+            "" | "on" | "true" | "1" | "enabled" | "enable" => Some(true),
+            _ => None,
+        }
+    })
+    .unwrap_or(true)
+}
+
+/// Returns the factor set via the `NTEST_TIMEOUT_SCALE` environment variable. Defaults to `1`.
+pub fn timeout_scale() -> f64 {
+    env_value(
+        TIMEOUT_SCALE_ENV,
+        "a positive number such as '3' or '0.5'",
+        |value| {
+            value
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|scale| scale.is_finite() && *scale > 0.0)
+        },
+    )
+    .unwrap_or(1.0)
+}
+
+/// Returns the `warn` threshold libtest uses for `--report-time` from `RUST_TEST_TIME_UNIT` or
+/// `RUST_TEST_TIME_INTEGRATION`. The variables have the format `<warn_ms>,<critical_ms>`.
+pub fn libtest_warn_threshold(integration: bool) -> Option<Duration> {
+    let key = if integration {
+        TEST_TIME_INTEGRATION_ENV
+    } else {
+        TEST_TIME_UNIT_ENV
+    };
+    env_value(key, "'<warn_ms>,<critical_ms>'", |value| {
+        let warn_ms = value.split(',').next().unwrap_or_default().trim();
+        warn_ms.parse::<u64>().ok().map(Duration::from_millis)
+    })
+}
+
+// Large factors saturate instead of overflowing the duration
+fn scale_duration(duration: Duration, scale: f64) -> Duration {
+    Duration::try_from_secs_f64(duration.as_secs_f64() * scale).unwrap_or(Duration::MAX)
+}
+
+// Parses an environment variable. An invalid value is ignored with a warning which is printed
+// once per value instead of failing every test with a timeout.
+fn env_value<T>(key: &'static str, expected: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    static REPORTED: Mutex<Vec<(&str, String)>> = Mutex::new(Vec::new());
+    let value = std::env::var(key).ok()?;
+    let parsed = parse(&value);
+    if parsed.is_none() {
+        let mut reported = REPORTED.lock().unwrap_or_else(|e| e.into_inner());
+        if !reported.iter().any(|(k, v)| *k == key && *v == value) {
+            eprintln!(
+                "[ntest] warning: ignoring invalid value '{}' for {}. Expected {}",
+                value, key, expected
+            );
+            reported.push((key, value));
+        }
+    }
+    parsed
+}
+
+/// Timeout configuration of a test function generated by the `#[timeout]` attribute.
+#[derive(Clone, Debug, Default)]
+pub struct TimeoutSpec {
+    /// Module path of the test function including the crate name.
+    pub path: &'static str,
+    /// The test fails if it takes longer.
+    pub fail: Option<Duration>,
+    /// A warning is printed if the test takes longer.
+    pub warn: Option<Duration>,
+    /// The test fails if its thread uses more CPU time.
+    pub cpu: Option<Duration>,
+    /// Selects `RUST_TEST_TIME_INTEGRATION` instead of `RUST_TEST_TIME_UNIT` as default for `warn`.
+    pub integration: bool,
+    /// The test is run again up to this many times if it fails or times out.
+    pub retries: u32,
+}
+
+// Thresholds of a test after the environment configuration was applied
+pub(crate) struct Thresholds {
+    pub warn: Option<Duration>,
+    pub fail: Option<Duration>,
+    pub cpu: Option<Duration>,
+}
+
+// Time a test used
+pub(crate) struct Usage {
+    pub elapsed: Duration,
+    pub cpu: Option<Duration>,
+    /// The test panicked in an assertion macro which already wrote the failure record.
+    pub assertion_reported: bool,
+}
+
+// Limit which was exceeded by a timed out test
+#[derive(Clone, Copy)]
+pub(crate) enum Exceeded {
+    WallClock,
+    CpuTime,
+}
+
+impl TimeoutSpec {
+    /// Name of the test as used by libtest.
+    pub fn name(&self) -> &'static str {
+        test_name(self.path)
+    }
+
+    /// Applies the environment configuration. Returns `None` if timeouts are disabled.
+    pub(crate) fn thresholds(&self) -> Option<Thresholds> {
+        if !timeouts_enabled() || tracer_attached() {
+            return None;
+        }
+        let scale = timeout_scale();
+        let warn = self
+            .warn
+            .or_else(|| libtest_warn_threshold(self.integration));
+        // Without CPU clock the CPU time limit is applied to the wall-clock time instead
+        let (fail, cpu) = match self.cpu {
+            Some(cpu) if cpu_time::SUPPORTED => (
+                Some(self.fail.unwrap_or(cpu * CPU_BACKSTOP_FACTOR)),
+                Some(cpu),
+            ),
+            Some(cpu) => (Some(self.fail.map_or(cpu, |fail| fail.min(cpu))), None),
+            None => (self.fail, None),
+        };
+        Some(Thresholds {
+            warn: warn.map(|warn| scale_duration(warn, scale)),
+            fail: fail.map(|fail| scale_duration(fail, scale)),
+            cpu: cpu.map(|cpu| scale_duration(cpu, scale)),
+        })
+    }
+
+    /// Prints a warning which bypasses the output capturing of libtest if the soft threshold was exceeded.
+    pub(crate) fn warn_if_slow(&self, thresholds: &Thresholds, elapsed: Duration) {
+        if let Some(warn) = thresholds.warn {
+            if elapsed > warn {
+                let _ = writeln!(
+                    std::io::stderr(),
+                    "[ntest] warning: slow test name=\"{}\" elapsed_ms={} warn_ms={} fail_ms={}",
+                    self.name(),
+                    elapsed.as_millis(),
+                    warn.as_millis(),
+                    thresholds
+                        .fail
+                        .map_or("none".to_string(), |fail| fail.as_millis().to_string())
+                );
+            }
+        }
+    }
+
+    /// Appends a record for the test to the `NTEST_REPORT` file.
+    pub(crate) fn report(
+        &self,
+        outcome: Outcome,
+        usage: &Usage,
+        thresholds: Option<&Thresholds>,
+        attempt: u32,
+        message: Option<&str>,
+    ) {
+        report::write(&Record {
+            name: self.name(),
+            source: "timeout",
+            outcome,
+            elapsed: Some(usage.elapsed),
+            budget: thresholds.and_then(|thresholds| thresholds.fail),
+            cpu: usage.cpu,
+            cpu_budget: thresholds.and_then(|thresholds| thresholds.cpu),
+            attempt: Some(attempt),
+            message,
+        });
+    }
+
+    /// Describes a timeout of the test.
+    pub(crate) fn timeout_message(
+        &self,
+        thresholds: &Thresholds,
+        usage: &Usage,
+        exceeded: Exceeded,
+        note: &str,
+    ) -> String {
+        match (exceeded, thresholds.cpu) {
+            (Exceeded::CpuTime, Some(cpu)) => format!(
+                "timeout: the function call used {} ms of CPU time and took {} ms. Max CPU time {} ms (annotated {} ms){}",
+                usage.cpu.unwrap_or_default().as_millis(),
+                usage.elapsed.as_millis(),
+                cpu.as_millis(),
+                self.cpu.unwrap_or_default().as_millis(),
+                note
+            ),
+            _ => format!(
+                "timeout: the function call took {} ms{}. Max time {} ms ({}){}",
+                usage.elapsed.as_millis(),
+                // The CPU time is only of interest if it is limited
+                usage.cpu.filter(|_| thresholds.cpu.is_some()).map_or(String::new(), |cpu| format!(
+                    " and used {} ms of CPU time",
+                    cpu.as_millis()
+                )),
+                thresholds.fail.unwrap_or_default().as_millis(),
+                match self.fail {
+                    Some(fail) => format!("annotated {} ms", fail.as_millis()),
+                    None => "wall-clock backstop of the CPU time limit".to_string(),
+                },
+                note
+            ),
+        }
+    }
+}
+
+// libtest names tests by their module path without the crate name
+pub(crate) fn test_name(path: &str) -> &str {
+    path.split_once("::").map_or(path, |(_, name)| name)
+}
+
+/// Returns `true` if a debugger or another tracer such as `strace` is attached to the process.
+/// Only detected on Linux via the `TracerPid` in `/proc/self/status`.
+#[cfg(target_os = "linux")]
+pub fn tracer_attached() -> bool {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find(|line| line.starts_with("TracerPid:"))
+                .and_then(|line| line["TracerPid:".len()..].trim().parse::<u32>().ok())
+        })
+        .is_some_and(|pid| pid != 0)
+}
+
+/// Returns `true` if a debugger or another tracer such as `strace` is attached to the process.
+/// Only detected on Linux via the `TracerPid` in `/proc/self/status`.
+#[cfg(not(target_os = "linux"))]
+pub fn tracer_attached() -> bool {
+    false
+}
+
+/// Runs the test function in a separate thread and panics if it does not finish within the budget.
+/// Time during which a tracer is attached is not counted against the budget.
+/// On timeout the cancellation token of the test thread is cancelled and the thread gets a short
+/// grace period to stop before the timeout panic is raised. A failed or timed out test is run again
+/// if the spec allows retries. `failure` describes a returned value which fails the test.
+#[track_caller]
+pub fn execute_test_with_timeout<T: Send + 'static>(
+    spec: TimeoutSpec,
+    code: fn() -> T,
+    failure: impl Fn(&T) -> Option<String>,
+) -> T {
+    let mut attempts = Attempts::new(&spec);
+    loop {
+        let attempt = match attempts.budget() {
+            Some(thresholds) => run_detached(&spec, thresholds, code),
+            None => Ok(run_inline(code, Instant::now())),
+        };
+        if let Some(output) = attempts.evaluate(attempt, &failure) {
+            return output;
+        }
+    }
+}
+
+/// Runs a test function which borrows data, such as its arguments, in a scoped thread and panics if
+/// it does not finish within the budget. Unlike [execute_test_with_timeout] the thread can not be
+/// detached. If it ignores the cancellation after a timeout, the timeout is printed right away and
+/// the test fails once the thread has finished.
+#[track_caller]
+pub fn execute_scoped_test_with_timeout<T: Send>(
+    spec: TimeoutSpec,
+    code: impl FnOnce() -> T + Send,
+    failure: impl Fn(&T) -> Option<String>,
+) -> T {
+    let mut attempts = Attempts::new(&spec);
+    let attempt = run_scoped_attempt(&spec, &attempts, code);
+    attempts
+        .evaluate(attempt, failure)
+        .expect("Retries require execute_scoped_test_with_retries")
+}
+
+/// Like [execute_scoped_test_with_timeout] but runs the test function again if it fails or times out.
+#[track_caller]
+pub fn execute_scoped_test_with_retries<T: Send>(
+    spec: TimeoutSpec,
+    code: impl Fn() -> T + Sync,
+    failure: impl Fn(&T) -> Option<String>,
+) -> T {
+    let mut attempts = Attempts::new(&spec);
+    loop {
+        let attempt = run_scoped_attempt(&spec, &attempts, &code);
+        if let Some(output) = attempts.evaluate(attempt, &failure) {
+            return output;
+        }
+    }
+}
+
+/// Error returned by [with_timeout] if the code did not finish in time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeoutError {
+    elapsed: Duration,
+    timeout: Duration,
+}
+
+impl TimeoutError {
+    /// Time which passed until the code was cancelled.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Effective timeout after the environment configuration such as `NTEST_TIMEOUT_SCALE` was applied.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl std::fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "timeout: the call took {} ms. Max time {} ms",
+            self.elapsed.as_millis(),
+            self.timeout.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for TimeoutError {}
+
+/// Runs the code in a separate thread and returns an error if it does not finish within the timeout.
+///
+/// The code may borrow local variables. Panics of the code are propagated to the caller.
+/// The timeout is adjusted by the `NTEST_TIMEOUT_SCALE` and `NTEST_TIMEOUT` environment variables
+/// like the `#[timeout]` attribute.
+///
+/// On timeout the [cancellation](crate::cancellation()) token of the thread is cancelled and the
+/// thread gets a short grace period to stop. The cancellation is cooperative: code which does not
+/// check the token, such as a blocking call, keeps running. Since the thread may borrow data of the
+/// caller it can not be left running in the background. In that case the timeout is printed right
+/// away and the error is returned once the thread has finished.
+///
+/// # Examples
+///
+/// ```
+/// use ntest::with_timeout;
+/// use std::time::Duration;
+///
+/// let values = vec![1, 2, 3];
+/// let sum = with_timeout(Duration::from_secs(1), || values.iter().sum::<u32>());
+/// assert_eq!(sum, Ok(6));
+///
+/// let result = with_timeout(Duration::from_millis(10), || loop {
+///     ntest::check_cancelled();
+/// });
+/// assert!(result.is_err());
+/// ```
+pub fn with_timeout<T: Send>(
+    timeout: Duration,
+    code: impl FnOnce() -> T + Send,
+) -> Result<T, TimeoutError> {
+    if !timeouts_enabled() || tracer_attached() {
+        return Ok(code());
+    }
+    let timeout = scale_duration(timeout, timeout_scale());
+    let thresholds = Thresholds {
+        warn: None,
+        fail: Some(timeout),
+        cpu: None,
+    };
+    let current = thread::current();
+    let timeout_error = |timed_out: &TimedOut| TimeoutError {
+        elapsed: timed_out.usage.elapsed,
+        timeout,
+    };
+    let outcome = run_scoped(current.name(), &thresholds, code, |timed_out| {
+        format!("{}{}", timeout_error(timed_out), timed_out.note)
+    });
+    match outcome {
+        Ok((Ok(output), _)) => Ok(output),
+        Ok((Err(panic_payload), _)) => panic::resume_unwind(panic_payload),
+        Err(timed_out) => Err(timeout_error(&timed_out)),
+    }
+}
+
+#[doc(hidden)]
+/// Helper for the `within!` macro which panics on timeout
+#[track_caller]
+pub fn execute_within<T: Send>(timeout: Duration, code: impl FnOnce() -> T + Send) -> T {
+    match with_timeout(timeout, code) {
+        Ok(output) => output,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn run_inline<T>(code: impl FnOnce() -> T, start: Instant) -> (thread::Result<T>, Usage) {
+    let (result, assertion_reported) = report::catch_test_panic(code);
+    let usage = Usage {
+        elapsed: start.elapsed(),
+        cpu: None,
+        assertion_reported,
+    };
+    (result, usage)
+}
+
+// The test thread is detached if it ignores the cancellation
+fn run_detached<T: Send + 'static>(
+    spec: &TimeoutSpec,
+    thresholds: &Thresholds,
+    code: fn() -> T,
+) -> Attempt<T> {
+    let start = Instant::now();
+    let worker = Worker::new();
+    let (sender, receiver) = mpsc::channel();
+    // The worker is named after the test like the threads of libtest
+    thread::Builder::new()
+        .name(spec.name().to_string())
+        .spawn({
+            let worker = worker.clone();
+            move || worker.run(code, sender)
+        })
+        .expect("Could not spawn the test thread");
+    worker.monitor(thresholds, &receiver, start)
+}
+
+fn run_scoped_attempt<T: Send>(
+    spec: &TimeoutSpec,
+    attempts: &Attempts,
+    code: impl FnOnce() -> T + Send,
+) -> Attempt<T> {
+    match attempts.budget() {
+        Some(thresholds) => run_scoped(Some(spec.name()), thresholds, code, |timed_out| {
+            format!(
+                "test '{}' failed: {}",
+                spec.name(),
+                attempts.describe_timeout(timed_out)
+            )
+        }),
+        None => Ok(run_inline(code, Instant::now())),
+    }
+}
+
+// Runs the code in a scoped thread. A thread which ignores the cancellation after a timeout can not
+// be detached since it borrows data of the caller. In that case the description of the timeout is
+// printed and the thread is joined before the timeout is returned.
+fn run_scoped<T: Send>(
+    name: Option<&str>,
+    thresholds: &Thresholds,
+    code: impl FnOnce() -> T + Send,
+    describe_timeout: impl FnOnce(&TimedOut) -> String,
+) -> Attempt<T> {
+    let start = Instant::now();
+    let worker = Worker::new();
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        let mut builder = thread::Builder::new();
+        if let Some(name) = name {
+            builder = builder.name(name.to_string());
+        }
+        let handle = builder
+            .spawn_scoped(scope, {
+                let worker = worker.clone();
+                move || worker.run(code, sender)
+            })
+            .expect("Could not spawn the test thread");
+        let mut outcome = worker.monitor(thresholds, &receiver, start);
+        if let Err(timed_out) = &mut outcome {
+            if !timed_out.stopped {
+                let _ = writeln!(
+                    std::io::stderr(),
+                    "[ntest] {}\n[ntest] The thread borrows data of the caller and ignored the cancellation. Waiting for it to finish.",
+                    describe_timeout(timed_out)
+                );
+                // Panics of the thread are caught by the worker
+                let _ = handle.join();
+                timed_out.note = timed_out.note.replacen(
+                    KEEPS_RUNNING,
+                    &format!(" and finished after {} ms", start.elapsed().as_millis()),
+                    1,
+                );
+            }
+        }
+        outcome
+    })
+}
+
+// Result of a test thread together with its CPU time
+type Finished<T> = (thread::Result<T>, Option<Duration>, bool);
+
+// State the test thread shares with the thread which monitors it
+#[derive(Clone)]
+struct Worker {
+    token: CancellationToken,
+    thread_id: Arc<AtomicI32>,
+    clock: Arc<OnceLock<ThreadCpuClock>>,
+}
+
+impl Worker {
+    fn new() -> Self {
+        Worker {
+            token: CancellationToken::new(),
+            thread_id: Arc::new(AtomicI32::new(0)),
+            clock: Arc::new(OnceLock::new()),
+        }
+    }
+
+    // Runs on the test thread
+    fn run<T>(self, code: impl FnOnce() -> T, sender: mpsc::Sender<Finished<T>>) {
+        self.thread_id
+            .store(stack_trace::current_thread_id(), Ordering::SeqCst);
+        let clock = ThreadCpuClock::current();
+        if let Some(clock) = clock {
+            let _ = self.clock.set(clock);
+        }
+        cancellation::set_current(self.token);
+        let (result, assertion_reported) = report::catch_test_panic(code);
+        let cpu = clock.and_then(|clock| clock.elapsed());
+        // Send will fail if receiver has already timed out or dropped - this is expected
+        let _ = sender.send((result, cpu, assertion_reported));
+    }
+
+    // Waits for the test thread. On timeout the test thread is cancelled.
+    fn monitor<T>(
+        &self,
+        thresholds: &Thresholds,
+        receiver: &mpsc::Receiver<Finished<T>>,
+        start: Instant,
+    ) -> Attempt<T> {
+        let poll_interval = if thresholds.cpu.is_some() {
+            CPU_POLL_INTERVAL
+        } else {
+            TRACER_POLL_INTERVAL
+        };
+        let mut remaining = thresholds.fail.unwrap_or_default();
+        loop {
+            let slice = remaining.min(poll_interval);
+            match receiver.recv_timeout(slice) {
+                Ok((result, cpu, assertion_reported)) => {
+                    let usage = Usage {
+                        elapsed: start.elapsed(),
+                        cpu,
+                        assertion_reported,
+                    };
+                    return Ok((result, usage));
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if tracer_attached() {
+                        continue;
+                    }
+                    remaining -= slice;
+                    let cpu = self.clock.get().and_then(|clock| clock.elapsed());
+                    let exceeded = match (cpu, thresholds.cpu) {
+                        (Some(cpu), Some(limit)) if cpu > limit => Exceeded::CpuTime,
+                        _ if remaining == Duration::from_secs(0) => Exceeded::WallClock,
+                        _ => continue,
+                    };
+                    let usage = Usage {
+                        elapsed: start.elapsed(),
+                        cpu,
+                        assertion_reported: false,
+                    };
+                    return Err(self.cancel(usage, exceeded, receiver));
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    panic!("Thread disconnected unexpectedly")
+                }
+            }
+        }
+    }
+
+    fn cancel<T>(
+        &self,
+        usage: Usage,
+        exceeded: Exceeded,
+        receiver: &mpsc::Receiver<Finished<T>>,
+    ) -> TimedOut {
+        let stack_trace = stack_trace::describe(self.thread_id.load(Ordering::SeqCst));
+        self.token.cancel();
+        let stopped = !matches!(
+            receiver.recv_timeout(CANCELLATION_GRACE_PERIOD),
+            Err(mpsc::RecvTimeoutError::Timeout)
+        );
+        let note = if stopped {
+            String::new()
+        } else {
+            format!(
+                ". The test thread ignored the cancellation{}",
+                KEEPS_RUNNING
+            )
+        };
+        TimedOut {
+            usage,
+            exceeded,
+            stopped,
+            note: format!("{}{}", note, stack_trace),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(fail: Option<Duration>, warn: Option<Duration>) -> TimeoutSpec {
+        TimeoutSpec {
+            path: "ntest::timeout::tests",
+            fail,
+            warn,
+            cpu: None,
+            integration: false,
+            retries: 0,
+        }
+    }
+
+    #[test]
+    fn returns_value_within_budget() {
+        let spec = spec(Some(Duration::from_secs(10)), None);
+        assert_eq!(execute_test_with_timeout(spec, || 42, |_| None), 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "timeout: the function call took")]
+    fn panics_on_timeout() {
+        let spec = spec(Some(Duration::from_millis(10)), None);
+        execute_test_with_timeout(spec, || thread::sleep(Duration::from_secs(1)), |_| None);
+    }
+
+    #[test]
+    fn timeout_message_mentions_cpu_time_only_with_cpu_limit() {
+        let usage = Usage {
+            elapsed: Duration::from_millis(20),
+            cpu: Some(Duration::from_millis(5)),
+            assertion_reported: false,
+        };
+        let mut thresholds = Thresholds {
+            warn: None,
+            fail: Some(Duration::from_millis(10)),
+            cpu: None,
+        };
+        let spec = spec(Some(Duration::from_millis(10)), None);
+        let message = spec.timeout_message(&thresholds, &usage, Exceeded::WallClock, "");
+        assert_eq!(
+            message,
+            "timeout: the function call took 20 ms. Max time 10 ms (annotated 10 ms)"
+        );
+        thresholds.cpu = Some(Duration::from_millis(1));
+        let message = spec.timeout_message(&thresholds, &usage, Exceeded::WallClock, "");
+        assert!(message.contains("took 20 ms and used 5 ms of CPU time."));
+    }
+
+    #[test]
+    fn scaled_duration_saturates() {
+        assert_eq!(
+            scale_duration(Duration::from_secs(2), 1.5),
+            Duration::from_secs(3)
+        );
+        assert_eq!(scale_duration(Duration::MAX, 3.0), Duration::MAX);
+        assert_eq!(scale_duration(Duration::from_secs(1), 1e300), Duration::MAX);
+    }
+
+    #[test]
+    fn invalid_environment_value_is_ignored() {
+        const KEY: &str = "NTEST_TEST_INVALID_ENVIRONMENT_VALUE";
+        std::env::set_var(KEY, "x");
+        assert_eq!(env_value(KEY, "a number", |v| v.parse::<u32>().ok()), None);
+        std::env::set_var(KEY, "3");
+        assert_eq!(
+            env_value(KEY, "a number", |v| v.parse::<u32>().ok()),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn with_timeout_borrows_locals() {
+        let mut values = vec![1, 2];
+        let len = with_timeout(Duration::from_secs(10), || {
+            values.push(3);
+            values.len()
+        });
+        assert_eq!(len, Ok(3));
+        assert_eq!(values, [1, 2, 3]);
+    }
+
+    #[test]
+    fn with_timeout_returns_error() {
+        let error = with_timeout(Duration::from_millis(10), || loop {
+            cancellation::check_cancelled();
+            thread::sleep(Duration::from_millis(1));
+        })
+        .unwrap_err();
+        assert!(error.elapsed() >= Duration::from_millis(10));
+        assert!(error.to_string().starts_with("timeout: the call took"));
+    }
+
+    #[test]
+    fn with_timeout_returns_error_for_blocking_code() {
+        let error = with_timeout(Duration::from_millis(10), || {
+            thread::sleep(Duration::from_millis(300))
+        })
+        .unwrap_err();
+        assert!(error.elapsed() >= Duration::from_millis(10));
+        assert!(error.elapsed() < Duration::from_millis(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "I am panicing")]
+    fn with_timeout_propagates_panics() {
+        let _ = with_timeout(Duration::from_secs(10), || panic!("I am panicing"));
+    }
+
+    #[test]
+    fn soft_timeout_passes() {
+        let spec = spec(None, Some(Duration::from_millis(1)));
+        execute_test_with_timeout(spec, || thread::sleep(Duration::from_millis(10)), |_| None);
+    }
+
+    #[test]
+    fn name_without_crate() {
+        assert_eq!(
+            test_name("ntest::timeout::tests::name"),
+            "timeout::tests::name"
+        );
+        assert_eq!(test_name("integration"), "integration");
+    }
+}
@@ -0,0 +1,49 @@
+use ntest::{test_case, test_matrix};
+use std::time::Duration;
+
+async fn double(x: u32) -> u32 {
+    tokio::task::yield_now().await;
+    x * 2
+}
+
+#[test_case(1, 2)]
+#[test_case(21, 42)]
+async fn runtime_of_feature(x: u32, expected: u32) {
+    assert_eq!(double(x).await, expected);
+}
+
+#[test_case(2 => 4)]
+#[test_case(5 => 10; "five")]
+async fn expected_result(x: u32) -> u32 {
+    double(x).await
+}
+
+#[test_case("7" => matches Ok(14))]
+#[test_case("x" => matches Err(_))]
+async fn expected_result_with_question_mark(x: &str) -> Result<u32, std::num::ParseIntError> {
+    let x = x.parse()?;
+    Ok(double(x).await)
+}
+
+#[test_case(1)]
+#[test_case(2, test_attr = tokio::test)]
+#[test_case_attr(tokio::test(flavor = "current_thread"))]
+async fn explicit_runtime(x: u32) {
+    assert!(double(x).await > x);
+}
+
+#[test_matrix([1, 2], [true, false])]
+#[test_case_timeout(1000)]
+async fn matrix_with_timeout(x: u32, sleep: bool) {
+    if sleep {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    assert_eq!(double(x).await, x + x);
+}
+
+#[test_case(10)]
+#[should_panic(expected = "timeout: the function call took")]
+#[test_case_timeout(10)]
+async fn timeout_of_async_test_case(x: u64) {
+    tokio::time::sleep(Duration::from_secs(x)).await;
+}
@@ -0,0 +1,134 @@
+use ntest::timeout;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+#[timeout(100)]
+#[tokio::test]
+async fn timeout_above_runtime_attribute() {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+}
+
+#[tokio::test]
+#[timeout(100)]
+async fn timeout_below_runtime_attribute() {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+}
+
+#[timeout(10)]
+#[tokio::test]
+#[should_panic(expected = "timeout: the function call took")]
+async fn timeout_above_runtime_attribute_fails() {
+    tokio::time::sleep(Duration::from_secs(10)).await;
+}
+
+#[tokio::test]
+#[timeout(10)]
+#[should_panic(expected = "timeout: the function call took")]
+async fn timeout_below_runtime_attribute_fails() {
+    tokio::time::sleep(Duration::from_secs(10)).await;
+}
+
+#[tokio::test]
+#[timeout(10)]
+#[should_panic(expected = "timeout: the function call took")]
+async fn blocking_body_below_runtime_attribute_fails() {
+    loop {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+#[timeout(1000)]
+fn sync_test_with_async_block() {
+    let body = async { 42 };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    assert_eq!(runtime.block_on(body), 42);
+}
+
+#[timeout("1s")]
+#[tokio::test]
+async fn timeout_with_result() -> Result<(), std::num::ParseIntError> {
+    let value: u32 = "42".parse()?;
+    assert_eq!(value, 42);
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic = "I am panicing"]
+#[timeout(1000)]
+async fn panic_in_future() {
+    panic!("I am panicing");
+}
+
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+struct DropGuard;
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn timed_out_future_is_cancelled() {
+    let spec = ntest::TimeoutSpec {
+        fail: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    let result = tokio::spawn(ntest::execute_future_with_timeout(
+        spec,
+        async {
+            let _guard = DropGuard;
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        },
+        |_| None,
+    ))
+    .await;
+    assert!(result.is_err());
+    assert!(DROPPED.load(Ordering::SeqCst));
+}
+
+static ABOVE_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[timeout(50, retries = 1)]
+#[tokio::test]
+async fn retries_above_runtime_attribute() {
+    if ABOVE_ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    }
+}
+
+static BELOW_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[tokio::test]
+#[timeout(50, retries = 1)]
+async fn retries_below_runtime_attribute() {
+    if BELOW_ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    }
+}
+
+#[test]
+fn timer_does_not_depend_on_the_runtime() {
+    // Without `enable_time` the timer of tokio panics
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let spec = ntest::TimeoutSpec {
+        fail: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    let panic_payload = std::panic::catch_unwind(|| {
+        runtime.block_on(ntest::execute_future_with_timeout(
+            spec,
+            std::future::pending::<()>(),
+            |_| None,
+        ))
+    })
+    .unwrap_err();
+    let message = panic_payload.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("timeout: the function call took"));
+}
@@ -1,5 +1,7 @@
 use ntest::test_case;
+use ntest::test_case_timeout;
 use ntest::timeout;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{thread, time};
 
 const TWO_HUNDRED: u64 = 200;
@@ -20,6 +22,25 @@ fn test_function(i: u64) {
     thread::sleep(sleep_time);
 }
 
+#[test_case(10)]
+#[test_case(200, timeout = "1s")]
+#[test_case(500, timeout = 100)]
+#[should_panic(expected = "Max time 100 ms")]
+fn test_case_timeout_keyword(i: u64) {
+    thread::sleep(time::Duration::from_millis(i));
+}
+
+#[test_case_timeout("100ms")]
+#[test_case(10)]
+#[test_case(500)]
+#[should_panic(expected = "Max time 100 ms")]
+#[test_case(200, timeout = "1s")]
+#[test_case(300)]
+#[timeout(1000)]
+fn test_case_default_timeout(i: u64) {
+    thread::sleep(time::Duration::from_millis(i));
+}
+
 #[test_case(1.2)]
 #[test_case(2.2)]
 fn test_f64(i: f64) {
@@ -33,7 +54,10 @@ fn test_int(i: i64) {
 }
 
 #[repr(u8)]
-enum Test { A = 200, B = 10 }
+enum Test {
+    A = 200,
+    B = 10,
+}
 #[test_case(Test::A)]
 #[timeout(100)]
 #[should_panic]
@@ -64,7 +88,7 @@ fn timeout() {
 #[should_panic]
 fn timeout_inf_loop() {
     let ten_millis = time::Duration::from_millis(10);
-    loop{
+    loop {
         thread::sleep(ten_millis);
     }
 }
@@ -89,7 +113,7 @@ async fn tokio_timeout() {
 #[should_panic]
 async fn tokio_should_panic_timeout() {
     let ten_millis = time::Duration::from_millis(10);
-    loop{
+    loop {
         thread::sleep(ten_millis);
     }
 }
@@ -100,3 +124,89 @@ async fn tokio_should_panic_timeout() {
 fn panic() {
     panic!();
 }
+
+static COOPERATIVE_LOOP_STOPPED: AtomicBool = AtomicBool::new(false);
+
+#[timeout(10)]
+fn cooperative_loop() {
+    let ten_millis = time::Duration::from_millis(10);
+    while !ntest::cancellation().is_cancelled() {
+        thread::sleep(ten_millis);
+    }
+    COOPERATIVE_LOOP_STOPPED.store(true, Ordering::SeqCst);
+}
+
+#[test]
+fn timeout_cancels_test_thread() {
+    assert!(std::panic::catch_unwind(cooperative_loop).is_err());
+    assert!(COOPERATIVE_LOOP_STOPPED.load(Ordering::SeqCst));
+}
+
+#[test]
+#[timeout(10)]
+#[should_panic(expected = "timeout: the function call took")]
+fn timeout_check_cancelled() {
+    let ten_millis = time::Duration::from_millis(10);
+    loop {
+        ntest::check_cancelled();
+        thread::sleep(ten_millis);
+    }
+}
+
+#[test]
+fn within_returns_value_of_block() {
+    let mut steps = vec![];
+    let first = ntest::within!("1s", {
+        steps.push("connect");
+        steps.len()
+    });
+    ntest::within!(1000, steps.push("send"));
+    assert_eq!(first, 1);
+    assert_eq!(steps, ["connect", "send"]);
+}
+
+#[test]
+#[should_panic(expected = "timeout: the call took")]
+fn within_exceeded() {
+    let ten_millis = time::Duration::from_millis(10);
+    ntest::within!(
+        time::Duration::from_millis(10),
+        loop {
+            ntest::check_cancelled();
+            thread::sleep(ten_millis);
+        }
+    );
+}
+
+#[timeout(100)]
+mod module_timeout {
+    use ntest::test_case;
+    use std::{thread, time};
+
+    #[test_case(10)]
+    #[test_case(500)]
+    #[should_panic(expected = "Max time 100 ms")]
+    #[test_case(200)]
+    #[ntest::timeout(1000)]
+    fn module_timeout_test_cases(i: u64) {
+        thread::sleep(time::Duration::from_millis(i));
+    }
+
+    #[test_case(10)]
+    #[test_case(200, timeout = "1s")]
+    fn module_timeout_test_case_keyword(i: u64) {
+        thread::sleep(time::Duration::from_millis(i));
+    }
+
+    #[test_case(200)]
+    #[ntest::test_case_timeout(1000)]
+    fn module_timeout_default_test_case_timeout(i: u64) {
+        thread::sleep(time::Duration::from_millis(i));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Max time 100 ms")]
+    async fn module_timeout_tokio() {
+        thread::sleep(time::Duration::from_millis(500));
+    }
+}
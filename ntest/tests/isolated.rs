@@ -0,0 +1,67 @@
+use ntest::{isolated, timeout};
+use std::{thread, time};
+
+#[test]
+#[isolated]
+fn isolated_passes() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[test]
+#[isolated]
+fn isolated_with_result() -> Result<(), String> {
+    Ok(())
+}
+
+#[test]
+#[isolated]
+#[should_panic(expected = "I am panicing")]
+fn isolated_panics() {
+    panic!("I am panicing");
+}
+
+#[test]
+#[isolated]
+#[should_panic(expected = "terminated abnormally")]
+fn isolated_abort() {
+    std::process::abort();
+}
+
+#[test]
+#[isolated]
+#[should_panic(expected = "exited the process before completion")]
+fn isolated_exit() {
+    std::process::exit(0);
+}
+
+#[test]
+#[ignore]
+#[isolated]
+fn isolated_returns_error() -> Result<(), String> {
+    Err("failed".to_string())
+}
+
+#[test]
+fn isolated_error_fails_test() {
+    assert!(std::panic::catch_unwind(isolated_returns_error).is_err());
+}
+
+#[test]
+#[isolated]
+#[timeout(100)]
+#[should_panic(expected = "timeout")]
+fn isolated_timeout() {
+    loop {
+        thread::sleep(time::Duration::from_millis(10));
+    }
+}
+
+#[test]
+#[timeout(100)]
+#[isolated]
+#[should_panic(expected = "timeout")]
+fn timeout_isolated() {
+    loop {
+        thread::sleep(time::Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,57 @@
+use ntest::{assert_true, timeout};
+use std::{env, fs, panic, thread, time};
+
+#[timeout(1000)]
+fn passes() {}
+
+#[timeout(1000)]
+fn fails() {
+    panic!("I am panicing");
+}
+
+#[timeout(10)]
+fn times_out() {
+    thread::sleep(time::Duration::from_millis(200));
+}
+
+#[timeout(1000)]
+fn assertion_fails() {
+    assert_true!(false);
+}
+
+// Only a single test in this file since the environment is shared by all tests of the binary
+#[test]
+fn report_records_outcomes() {
+    let path = env::temp_dir().join(format!("ntest-report-{}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+    env::set_var("NTEST_REPORT", &path);
+
+    passes();
+    assert!(panic::catch_unwind(fails).is_err());
+    assert!(panic::catch_unwind(times_out).is_err());
+    assert!(panic::catch_unwind(|| assert_true!(false)).is_err());
+    assert!(panic::catch_unwind(assertion_fails).is_err());
+
+    env::remove_var("NTEST_REPORT");
+    let report = fs::read_to_string(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    let records: Vec<&str> = report.lines().collect();
+    assert_eq!(records.len(), 5, "{}", report);
+    assert!(records[0]
+        .starts_with(r#"{"name":"passes","source":"timeout","outcome":"pass","elapsed_ms":"#));
+    assert!(records[0].contains(r#""budget_ms":1000,"cpu_ms":"#));
+    assert!(records[0].ends_with(r#""message":null}"#));
+    assert!(records[1].starts_with(r#"{"name":"fails","source":"timeout","outcome":"fail","#));
+    assert!(records[1].ends_with(r#""message":"I am panicing"}"#));
+    assert!(
+        records[2].starts_with(r#"{"name":"times_out","source":"timeout","outcome":"timeout","#)
+    );
+    assert!(records[2].contains(r#""budget_ms":10,"cpu_ms":"#));
+    assert!(records[2].contains(r#""message":"timeout: the function call took"#));
+    assert!(records[3].starts_with(
+        r#"{"name":"report_records_outcomes","source":"assert_true","outcome":"fail","elapsed_ms":null"#
+    ));
+    // The assertion writes the only record of a test with a timeout
+    assert!(records[4]
+        .starts_with(r#"{"name":"assertion_fails","source":"assert_true","outcome":"fail","#));
+}
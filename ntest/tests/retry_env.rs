@@ -0,0 +1,21 @@
+use ntest::retry;
+use std::env;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[retry(2)]
+fn fails_once() {
+    assert!(ATTEMPTS.fetch_add(1, Ordering::SeqCst) % 2 == 1);
+}
+
+// Only a single test in this file since the environment is shared by all tests of the binary
+#[test]
+fn environment_disables_retries() {
+    fails_once();
+    assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 2);
+
+    env::set_var("NTEST_RETRIES", "off");
+    assert!(std::panic::catch_unwind(fails_once).is_err());
+    assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+}
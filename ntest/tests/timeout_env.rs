@@ -0,0 +1,20 @@
+use ntest::timeout;
+use std::{env, thread, time};
+
+#[timeout(20)]
+fn sleep_fifty_millis() {
+    thread::sleep(time::Duration::from_millis(50));
+}
+
+// Only a single test in this file since the environment is shared by all tests of the binary
+#[test]
+fn environment_configures_timeout() {
+    env::set_var("NTEST_TIMEOUT_SCALE", "10");
+    sleep_fifty_millis();
+
+    env::set_var("NTEST_TIMEOUT_SCALE", "0.1");
+    assert!(std::panic::catch_unwind(sleep_fifty_millis).is_err());
+
+    env::set_var("NTEST_TIMEOUT", "off");
+    sleep_fifty_millis();
+}
@@ -0,0 +1,84 @@
+//! Part of the ntest library. Helper functions which are shared by the procedural macros of ntest.
+
+use proc_macro2::Span;
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::quote;
+
+/// Combines all errors into one which reports every error in a single compilation pass.
+pub fn combine_errors(errors: Vec<syn::Error>) -> syn::Error {
+    let mut errors = errors.into_iter();
+    let mut combined = errors
+        .next()
+        .unwrap_or_else(|| syn::Error::new(Span::call_site(), "Unexpected error"));
+    for error in errors {
+        combined.combine(error);
+    }
+    combined
+}
+
+/// Returns the combined errors if there are any.
+pub fn check_errors(errors: Vec<syn::Error>) -> syn::Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(combine_errors(errors))
+    }
+}
+
+/// Path of the `ntest` crate which contains the runtime of the generated code.
+pub fn ntest_path() -> syn::Result<proc_macro2::TokenStream> {
+    match crate_name("ntest") {
+        Ok(FoundCrate::Name(name)) => {
+            let ident = syn::Ident::new(&name, Span::call_site());
+            Ok(quote! { ::#ident })
+        }
+        Ok(FoundCrate::Itself) => Ok(quote! { crate }),
+        Err(_) => Err(syn::Error::new(
+            Span::call_site(),
+            "The `ntest` crate was not found. Add `ntest` to the dependencies in Cargo.toml.",
+        )),
+    }
+}
+
+/// Matches attribute paths such as `test_case` or `ntest::test_case` by their last segment.
+pub fn path_ends_with(path: &syn::Path, ident: &str) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_last_segment_of_path() {
+        assert!(path_ends_with(
+            &syn::parse_quote! { test_case },
+            "test_case"
+        ));
+        assert!(path_ends_with(
+            &syn::parse_quote! { ::ntest::test_case },
+            "test_case"
+        ));
+        assert!(!path_ends_with(
+            &syn::parse_quote! { test_case::other },
+            "test_case"
+        ));
+    }
+
+    #[test]
+    fn combines_all_errors() {
+        let errors = vec![
+            syn::Error::new(Span::call_site(), "first"),
+            syn::Error::new(Span::call_site(), "second"),
+        ];
+        let messages: Vec<String> = check_errors(errors)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(messages, ["first", "second"]);
+        assert!(check_errors(vec![]).is_ok());
+    }
+}
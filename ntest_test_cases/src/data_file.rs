@@ -0,0 +1,377 @@
+//! Tests generated from files for `#[test_cases_from]` and `#[test_files]`. The paths are relative
+//! to the directory of the `Cargo.toml` of the tested crate.
+
+use crate::{collect_function_arg_idents, glob, naming, syn_helper};
+use ntest_proc_macro_helper::{check_errors, combine_errors};
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+
+mod table;
+
+pub fn expand_test_cases_from(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut errors = vec![];
+    let args = parse_data_file_args(attr, &mut errors);
+    let input = match syn::parse2::<syn::ItemFn>(item) {
+        Ok(input) => input,
+        Err(e) => {
+            errors.push(e);
+            return Err(combine_errors(errors));
+        }
+    };
+    let (fn_args_idents, fn_args_ty) = collect_function_arg_idents(&input, &mut errors);
+    let (path, name_column) = match args {
+        Some(args) => args,
+        None => return Err(combine_errors(errors)),
+    };
+    check_errors(errors)?;
+
+    let file = manifest_dir(&path)?.join(path.value());
+    let text = std::fs::read_to_string(&file).map_err(|e| {
+        syn::Error::new_spanned(
+            &path,
+            format!("Failed to read data file `{}`: {}", file.display(), e),
+        )
+    })?;
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    // Errors point at the line of the data file
+    let row_error = |line: usize, message: &str| match line {
+        0 => syn::Error::new_spanned(&path, message),
+        _ => syn::Error::new_spanned(&path, format!("{}:{}: {}", path.value(), line, message)),
+    };
+    let rows = table::parse_rows(extension, &text).map_err(|e| row_error(e.line, &e.message))?;
+
+    let fn_name = &input.sig.ident;
+    let fn_body = &input.block;
+    let fn_return = &input.sig.output;
+    let attributes = &input.attrs;
+    let mut errors = vec![];
+    let mut names = vec![];
+    let mut cases = vec![];
+    for (index, row) in rows.iter().enumerate() {
+        let name = match &name_column {
+            None => format!("{}_{}", fn_name, index),
+            Some(column) => match row.get(&column.value()) {
+                Some(value) => naming::cap_length(format!("{}_{}", fn_name, value.to_identifier())),
+                None => {
+                    errors.push(row_error(
+                        row.line,
+                        &format!("Missing column `{}` for the test name.", column.value()),
+                    ));
+                    continue;
+                }
+            },
+        };
+        let mut values = vec![];
+        for (ident, ty) in fn_args_idents.iter().zip(&fn_args_ty) {
+            let column = syn::ext::IdentExt::unraw(ident).to_string();
+            match row.get(&column).map(|value| value.to_tokens(Some(ty))) {
+                Some(Ok(value)) => values.push(value),
+                Some(Err(message)) => errors.push(row_error(
+                    row.line,
+                    &format!("Column `{}`: {}", column, message),
+                )),
+                None => errors.push(row_error(
+                    row.line,
+                    &format!("Missing column `{}`.", column),
+                )),
+            }
+        }
+        names.push(name);
+        cases.push(values);
+    }
+    check_errors(errors)?;
+
+    let mut tests = proc_macro2::TokenStream::new();
+    for (name, values) in naming::number_collisions(names).into_iter().zip(cases) {
+        let test_name = syn::Ident::new(&name, Span::call_site());
+        tests.extend(quote! {
+            #[test]
+            #[allow(non_snake_case)]
+            #(#attributes)*
+            fn #test_name() #fn_return {
+                #(let #fn_args_idents: #fn_args_ty = #values;)*
+                #fn_body
+            }
+        });
+    }
+
+    let file = file.display().to_string();
+    Ok(quote! {
+        // Rebuilds the tests once the data file changes
+        const _: &[u8] = include_bytes!(#file);
+        #tests
+    })
+}
+
+// Path of the data file and the column which names the tests
+fn parse_data_file_args(
+    attr: proc_macro2::TokenStream,
+    errors: &mut Vec<syn::Error>,
+) -> Option<(syn::LitStr, Option<syn::LitStr>)> {
+    let args = match Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated.parse2(attr) {
+        Ok(args) => args,
+        Err(e) => {
+            errors.push(e);
+            return None;
+        }
+    };
+    let mut args = args.into_iter();
+    let path = match args.next() {
+        Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(path),
+            ..
+        })) => Some(path),
+        arg => {
+            let message =
+                "Expected the path of a data file such as #[test_cases_from(\"tests/data/cases.csv\")].";
+            errors.push(match arg {
+                Some(arg) => syn::Error::new_spanned(arg, message),
+                None => syn::Error::new(Span::call_site(), message),
+            });
+            None
+        }
+    };
+    let mut name_column = None;
+    for arg in args {
+        let assign = match &arg {
+            syn::Expr::Assign(assign) if syn_helper::is_ident(&assign.left, "name") => assign,
+            _ => {
+                errors.push(syn::Error::new_spanned(
+                    arg,
+                    "Unexpected argument. Expected `name = \"column\"`.",
+                ));
+                continue;
+            }
+        };
+        match &*assign.right {
+            _ if name_column.is_some() => errors.push(syn::Error::new_spanned(
+                assign,
+                "Test name can only be defined once.",
+            )),
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(column),
+                ..
+            }) => name_column = Some(column.clone()),
+            right => errors.push(syn::Error::new_spanned(
+                right,
+                "Unexpected type for the name column. Expected string.",
+            )),
+        }
+    }
+    path.map(|path| (path, name_column))
+}
+
+pub fn expand_test_files(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut errors = vec![];
+    let pattern = match syn::parse2::<syn::LitStr>(attr) {
+        Ok(pattern) => Some(pattern),
+        Err(e) => {
+            errors.push(syn::Error::new(
+                e.span(),
+                "Expected a file pattern such as #[test_files(\"tests/fixtures/*.json\")].",
+            ));
+            None
+        }
+    };
+    let input = match syn::parse2::<syn::ItemFn>(item) {
+        Ok(input) => input,
+        Err(e) => {
+            errors.push(e);
+            return Err(combine_errors(errors));
+        }
+    };
+    let (fn_args_idents, fn_args_ty) = collect_function_arg_idents(&input, &mut errors);
+    for (ident, ty) in fn_args_idents.iter().zip(&fn_args_ty) {
+        if ident != "path" && ident != "contents" {
+            errors.push(syn::Error::new_spanned(
+                ident,
+                "Unexpected parameter. Expected `path: &Path` and optionally `contents: &str`.",
+            ));
+        } else if ident == "contents" && !is_reference_to(ty, "str") && !is_byte_slice(ty) {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                "Unexpected type for the contents. Expected `&str` or `&[u8]`.",
+            ));
+        }
+    }
+    let pattern = match pattern {
+        Some(pattern) => pattern,
+        None => return Err(combine_errors(errors)),
+    };
+    check_errors(errors)?;
+
+    let (base, files) =
+        glob::find_files(&manifest_dir(&pattern)?, &pattern.value()).map_err(|e| {
+            syn::Error::new_spanned(&pattern, format!("Failed to search for files: {}", e))
+        })?;
+    if files.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &pattern,
+            format!("No files match `{}`.", pattern.value()),
+        ));
+    }
+
+    let fn_name = &input.sig.ident;
+    let fn_body = &input.block;
+    let fn_return = &input.sig.output;
+    let attributes = &input.attrs;
+    let names = naming::number_collisions(
+        files
+            .iter()
+            .map(|file| {
+                let relative = file
+                    .strip_prefix(&base)
+                    .unwrap_or(file)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                naming::cap_length(format!(
+                    "{}_{}",
+                    fn_name,
+                    syn_helper::string_to_identifier(&relative)
+                ))
+            })
+            .collect(),
+    );
+    let mut result = proc_macro2::TokenStream::new();
+    for (file, name) in files.iter().zip(names) {
+        let test_name = syn::Ident::new(&name, Span::call_site());
+        let file = file.display().to_string();
+        // Including the contents rebuilds the test once the file changes
+        let values = fn_args_idents.iter().zip(&fn_args_ty).map(|(ident, ty)| {
+            if ident == "path" {
+                quote! { ::std::path::Path::new(#file) }
+            } else if is_byte_slice(ty) {
+                quote! { include_bytes!(#file) }
+            } else {
+                quote! { include_str!(#file) }
+            }
+        });
+        let rebuild = if fn_args_idents.iter().any(|ident| ident == "contents") {
+            quote! {}
+        } else {
+            quote! { const _: &[u8] = include_bytes!(#file); }
+        };
+        result.extend(quote! {
+            #[test]
+            #[allow(non_snake_case)]
+            #(#attributes)*
+            fn #test_name() #fn_return {
+                #rebuild
+                #(let #fn_args_idents: #fn_args_ty = #values;)*
+                #fn_body
+            }
+        });
+    }
+    Ok(result)
+}
+
+// Directory of the `Cargo.toml` of the tested crate. Errors point at the path of the attribute.
+fn manifest_dir(path: &syn::LitStr) -> syn::Result<std::path::PathBuf> {
+    std::env::var("CARGO_MANIFEST_DIR")
+        .map(std::path::PathBuf::from)
+        .map_err(|_| {
+            syn::Error::new_spanned(
+                path,
+                "The environment variable CARGO_MANIFEST_DIR is not set.",
+            )
+        })
+}
+
+// Type such as `&str` for the name `str`
+fn is_reference_to(ty: &syn::Type, name: &str) -> bool {
+    match ty {
+        syn::Type::Reference(reference) => {
+            matches!(&*reference.elem, syn::Type::Path(path) if path.path.is_ident(name))
+        }
+        _ => false,
+    }
+}
+
+fn is_byte_slice(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(reference) => match &*reference.elem {
+            syn::Type::Slice(slice) => {
+                matches!(&*slice.elem, syn::Type::Path(path) if path.path.is_ident("u8"))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::error_messages;
+
+    #[test]
+    fn reports_invalid_rows_of_data_files() {
+        let messages = error_messages(expand_test_cases_from(
+            quote! { "tests/data/invalid.csv", name = "z" },
+            quote! {
+                fn test(x: u32, y: u32, z: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "tests/data/invalid.csv:2: Missing column `z` for the test name.",
+                "tests/data/invalid.csv:3: Missing column `z` for the test name.",
+            ]
+        );
+        let messages = error_messages(expand_test_cases_from(
+            quote! { "tests/data/invalid.csv" },
+            quote! {
+                fn test(x: u32, y: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            ["tests/data/invalid.csv:3: Column `y`: `?` is not a valid rust expression."]
+        );
+        let messages = error_messages(expand_test_cases_from(
+            quote! { "tests/data/missing.csv" },
+            quote! {
+                fn test(x: u32) {}
+            },
+        ));
+        assert!(messages[0].starts_with("Failed to read data file"));
+    }
+
+    #[test]
+    fn reports_invalid_test_file_parameters() {
+        let messages = error_messages(expand_test_files(
+            quote! { "tests/fixtures/**/*.json" },
+            quote! {
+                fn test(path: &Path, contents: String, size: u64) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Unexpected type for the contents. Expected `&str` or `&[u8]`.",
+                "Unexpected parameter. Expected `path: &Path` and optionally `contents: &str`.",
+            ]
+        );
+        let messages = error_messages(expand_test_files(
+            quote! { "tests/fixtures/**/*.yaml" },
+            quote! {
+                fn test(path: &Path) {}
+            },
+        ));
+        assert_eq!(messages, ["No files match `tests/fixtures/**/*.yaml`."]);
+    }
+}
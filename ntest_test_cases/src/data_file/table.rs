@@ -0,0 +1,728 @@
+//! Tables of test cases loaded from CSV, JSON, or TOML files for `#[test_cases_from]`.
+//!
+//! Only the parts of the formats which describe a flat table are supported:
+//! - CSV: a header line with the column names followed by one line per row. Fields may be quoted.
+//! - JSON: an array of objects. Every object is a row.
+//! - TOML: an array of tables such as `[[case]]`. Every table is a row.
+
+use crate::syn_helper;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+
+/// Value of a single column of a row.
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    /// Untyped CSV field. It is converted according to the type of the function parameter.
+    Field(String),
+    Str(String),
+    /// Number or boolean in its rust source form.
+    Literal(String),
+    Null,
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Rust expression of the value for a parameter of the given type. The type selects between
+    /// string and expression for CSV fields, and wraps values of `Option` parameters in `Some`.
+    pub fn to_tokens(&self, ty: Option<&syn::Type>) -> Result<TokenStream, String> {
+        if let Some(inner) = ty.and_then(|ty| generic_argument(ty, "Option")) {
+            return match self {
+                Value::Null => Ok(quote! { None }),
+                Value::Field(field) if field.is_empty() => Ok(quote! { None }),
+                value => {
+                    let value = value.to_tokens(Some(inner))?;
+                    Ok(quote! { Some(#value) })
+                }
+            };
+        }
+        match self {
+            Value::Field(field) if !ty.is_some_and(is_string_type) => {
+                syn::parse_str::<syn::Expr>(field)
+                    .map(|expr| quote! { #expr })
+                    .map_err(|_| format!("`{}` is not a valid rust expression.", field))
+            }
+            Value::Field(string) | Value::Str(string) => {
+                let lit = syn::LitStr::new(string, Span::call_site());
+                match ty.and_then(path_ident) {
+                    Some(ident) if ident == "String" => Ok(quote! { String::from(#lit) }),
+                    _ => Ok(quote! { #lit }),
+                }
+            }
+            Value::Literal(literal) => syn::parse_str::<syn::Expr>(literal)
+                .map(|expr| quote! { #expr })
+                .map_err(|_| format!("`{}` is not a valid rust literal.", literal)),
+            Value::Null => Ok(quote! { None }),
+            Value::Array(elements) => {
+                let element_ty = ty.and_then(|ty| generic_argument(ty, "Vec"));
+                let elements = elements
+                    .iter()
+                    .map(|element| element.to_tokens(element_ty))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(quote! { vec![#(#elements),*] })
+            }
+        }
+    }
+
+    /// Part of a test name derived from the value.
+    pub fn to_identifier(&self) -> String {
+        match self {
+            Value::Field(string) | Value::Str(string) | Value::Literal(string) => {
+                syn_helper::string_to_identifier(string)
+            }
+            Value::Null => "None".to_string(),
+            Value::Array(elements) => elements
+                .iter()
+                .map(Value::to_identifier)
+                .collect::<Vec<_>>()
+                .join("_"),
+        }
+    }
+}
+
+// Last identifier of the path of a type such as `String` for `std::string::String` or `str` for `&str`
+fn path_ident(ty: &syn::Type) -> Option<&syn::Ident> {
+    match ty {
+        syn::Type::Reference(reference) => path_ident(&reference.elem),
+        syn::Type::Path(path) => path.path.segments.last().map(|segment| &segment.ident),
+        _ => None,
+    }
+}
+
+fn is_string_type(ty: &syn::Type) -> bool {
+    path_ident(ty).is_some_and(|ident| ident == "str" || ident == "String")
+}
+
+// Type argument of a generic type such as `u32` of `Option<u32>`
+fn generic_argument<'a>(ty: &'a syn::Type, name: &str) -> Option<&'a syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) => {
+            arguments.args.iter().find_map(|argument| match argument {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Row {
+    /// Line of the file on which the row starts. The first line is `1`.
+    pub line: usize,
+    pub columns: Vec<(String, Value)>,
+}
+
+impl Row {
+    pub fn get(&self, column: &str) -> Option<&Value> {
+        self.columns
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Parses the rows of a file with the extension `csv`, `json`, or `toml`.
+pub fn parse_rows(extension: &str, text: &str) -> Result<Vec<Row>, ParseError> {
+    match extension {
+        "csv" => parse_csv(text),
+        "json" => Cursor::new(text).parse_json_rows(),
+        "toml" => Cursor::new(text).parse_toml_rows(),
+        _ => Err(ParseError {
+            line: 0,
+            message: "Unsupported file type. Expected a `.csv`, `.json`, or `.toml` file."
+                .to_string(),
+        }),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Row>, ParseError> {
+    let mut records = Cursor::new(text).parse_csv_records()?.into_iter();
+    let header = match records.next() {
+        Some((_, header)) => header,
+        None => return Ok(vec![]),
+    };
+    let mut rows = vec![];
+    for (line, fields) in records {
+        if fields.len() != header.len() {
+            return Err(ParseError {
+                line,
+                message: format!(
+                    "Expected {} fields like the header, found {}.",
+                    header.len(),
+                    fields.len()
+                ),
+            });
+        }
+        rows.push(Row {
+            line,
+            columns: header
+                .iter()
+                .cloned()
+                .zip(fields.into_iter().map(Value::Field))
+                .collect(),
+        });
+    }
+    Ok(rows)
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Cursor {
+    fn new(text: &str) -> Self {
+        Cursor {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", expected)))
+        }
+    }
+
+    fn error(&self, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        match self.peek() {
+            Some(c) => self.error(format!("Expected {}, found `{}`.", expected, c)),
+            None => self.error(format!("Expected {}, found the end of the file.", expected)),
+        }
+    }
+
+    // Characters until the first one which does not satisfy the predicate
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut taken = String::new();
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            taken.push(c);
+            self.next();
+        }
+        taken
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    // Records with the line they start on. Empty lines are skipped.
+    fn parse_csv_records(&mut self) -> Result<Vec<(usize, Vec<String>)>, ParseError> {
+        let mut records = vec![];
+        while self.peek().is_some() {
+            if self.eat('\n') || self.eat('\r') {
+                continue;
+            }
+            let line = self.line;
+            let mut fields = vec![];
+            loop {
+                fields.push(self.parse_csv_field()?);
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            records.push((line, fields));
+        }
+        Ok(records)
+    }
+
+    fn parse_csv_field(&mut self) -> Result<String, ParseError> {
+        self.take_while(|c| c == ' ' || c == '\t');
+        if !self.eat('"') {
+            return Ok(self
+                .take_while(|c| c != ',' && c != '\n' && c != '\r')
+                .trim()
+                .to_string());
+        }
+        let line = self.line;
+        let mut field = String::new();
+        loop {
+            match self.next() {
+                // A doubled quote is an escaped quote
+                Some('"') if self.eat('"') => field.push('"'),
+                Some('"') => break,
+                Some(c) => field.push(c),
+                None => {
+                    return Err(ParseError {
+                        line,
+                        message: "Unterminated quoted field.".to_string(),
+                    })
+                }
+            }
+        }
+        self.take_while(|c| c == ' ' || c == '\t');
+        match self.peek() {
+            None | Some(',') | Some('\n') | Some('\r') => Ok(field),
+            Some(_) => Err(self.unexpected("`,` after the quoted field")),
+        }
+    }
+
+    fn parse_json_rows(&mut self) -> Result<Vec<Row>, ParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some('[') {
+            return Err(self.unexpected("an array of objects"));
+        }
+        let rows = self.parse_json_sequence('[', ']', |cursor| {
+            if cursor.peek() != Some('{') {
+                return Err(cursor.unexpected("an object"));
+            }
+            let line = cursor.line;
+            let columns = cursor.parse_json_sequence('{', '}', |cursor| {
+                let key = match cursor.peek() {
+                    Some('"') => cursor.parse_json_string()?,
+                    _ => return Err(cursor.unexpected("a string as key")),
+                };
+                cursor.skip_whitespace();
+                cursor.expect(':')?;
+                cursor.skip_whitespace();
+                Ok((key, cursor.parse_json_value()?))
+            })?;
+            Ok(Row { line, columns })
+        })?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(rows),
+            Some(_) => Err(self.unexpected("the end of the file")),
+        }
+    }
+
+    // Comma separated elements between the opening and closing characters
+    fn parse_json_sequence<T>(
+        &mut self,
+        open: char,
+        close: char,
+        mut element: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        self.expect(open)?;
+        self.skip_whitespace();
+        let mut elements = vec![];
+        if self.eat(close) {
+            return Ok(elements);
+        }
+        loop {
+            self.skip_whitespace();
+            elements.push(element(self)?);
+            self.skip_whitespace();
+            if self.eat(close) {
+                return Ok(elements);
+            }
+            if !self.eat(',') {
+                return Err(self.unexpected(&format!("`,` or `{}`", close)));
+            }
+        }
+    }
+
+    fn parse_json_value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some('"') => Ok(Value::Str(self.parse_json_string()?)),
+            Some('[') => Ok(Value::Array(self.parse_json_sequence(
+                '[',
+                ']',
+                Self::parse_json_value,
+            )?)),
+            Some('{') => Err(self.error("Nested objects are not supported.".to_string())),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let number = self.take_while(|c| {
+                    c.is_ascii_digit() || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E'
+                });
+                Ok(Value::Literal(number))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                match self.take_while(|c| c.is_ascii_alphabetic()).as_str() {
+                    "null" => Ok(Value::Null),
+                    word @ "true" | word @ "false" => Ok(Value::Literal(word.to_string())),
+                    word => Err(self.error(format!("Unexpected value `{}`.", word))),
+                }
+            }
+            _ => Err(self.unexpected("a value")),
+        }
+    }
+
+    fn parse_json_string(&mut self) -> Result<String, ParseError> {
+        self.parse_quoted('"', true)
+    }
+
+    // Quoted string. Escape sequences are shared by JSON and the basic strings of TOML.
+    fn parse_quoted(&mut self, quote: char, escapes: bool) -> Result<String, ParseError> {
+        self.expect(quote)?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some(c) if c == quote => return Ok(string),
+                Some('\\') if escapes => string.push(self.parse_escape()?),
+                Some('\n') | None => {
+                    return Err(self.error("Unterminated string.".to_string()));
+                }
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, ParseError> {
+        let escaped = match self.next() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let high = self.parse_hex(4)?;
+                // Characters outside of the basic plane are encoded as surrogate pair in JSON
+                let code = if (0xD800..0xDC00).contains(&high) && self.eat('\\') && self.eat('u') {
+                    let low = self.parse_hex(4)?;
+                    0x10000 + ((high - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF)
+                } else {
+                    high
+                };
+                return std::char::from_u32(code)
+                    .ok_or_else(|| self.error(format!("Invalid unicode escape `{:x}`.", code)));
+            }
+            Some('U') => {
+                let code = self.parse_hex(8)?;
+                return std::char::from_u32(code)
+                    .ok_or_else(|| self.error(format!("Invalid unicode escape `{:x}`.", code)));
+            }
+            _ => return Err(self.error("Invalid escape sequence.".to_string())),
+        };
+        Ok(escaped)
+    }
+
+    fn parse_hex(&mut self, digits: usize) -> Result<u32, ParseError> {
+        let mut code = 0;
+        for _ in 0..digits {
+            match self.next().and_then(|c| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("Invalid unicode escape.".to_string())),
+            }
+        }
+        Ok(code)
+    }
+
+    fn skip_toml_whitespace(&mut self) {
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('#') {
+                return;
+            }
+            self.take_while(|c| c != '\n');
+        }
+    }
+
+    // Whitespace and a comment up to the end of the line
+    fn expect_toml_line_end(&mut self) -> Result<(), ParseError> {
+        self.take_while(|c| c == ' ' || c == '\t');
+        if self.peek() == Some('#') {
+            self.take_while(|c| c != '\n');
+        }
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.next();
+                Ok(())
+            }
+            Some(_) => Err(self.unexpected("the end of the line")),
+        }
+    }
+
+    fn parse_toml_rows(&mut self) -> Result<Vec<Row>, ParseError> {
+        let mut rows: Vec<Row> = vec![];
+        // Name of the array of tables
+        let mut table: Option<String> = None;
+        loop {
+            self.skip_toml_whitespace();
+            match self.peek() {
+                None => return Ok(rows),
+                Some('[') => {
+                    let line = self.line;
+                    self.next();
+                    if !self.eat('[') {
+                        return Err(self.error(
+                            "Tables are not supported. Expected an array of tables such as `[[case]]`."
+                                .to_string(),
+                        ));
+                    }
+                    let name = self.parse_toml_key()?;
+                    self.expect(']')?;
+                    self.expect(']')?;
+                    if table.get_or_insert_with(|| name.clone()) != &name {
+                        return Err(self.error(format!(
+                            "Expected a single array of tables. Found `{}` after `{}`.",
+                            name,
+                            table.unwrap_or_default()
+                        )));
+                    }
+                    self.expect_toml_line_end()?;
+                    rows.push(Row {
+                        line,
+                        columns: vec![],
+                    });
+                }
+                Some(_) => {
+                    let key = self.parse_toml_key()?;
+                    let row = match rows.last_mut() {
+                        Some(row) => row,
+                        None => {
+                            return Err(self.error(format!(
+                                "Key `{}` outside of an array of tables such as `[[case]]`.",
+                                key
+                            )))
+                        }
+                    };
+                    if row.get(&key).is_some() {
+                        return Err(self.error(format!("Duplicate key `{}`.", key)));
+                    }
+                    self.expect('=')?;
+                    self.take_while(|c| c == ' ' || c == '\t');
+                    let value = self.parse_toml_value()?;
+                    row.columns.push((key, value));
+                    self.expect_toml_line_end()?;
+                }
+            }
+        }
+    }
+
+    // Bare or quoted key. Surrounding whitespace is skipped.
+    fn parse_toml_key(&mut self) -> Result<String, ParseError> {
+        self.take_while(|c| c == ' ' || c == '\t');
+        let key = match self.peek() {
+            Some('"') => self.parse_quoted('"', true)?,
+            Some('\'') => self.parse_quoted('\'', false)?,
+            _ => {
+                let key = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if key.is_empty() {
+                    return Err(self.unexpected("a key"));
+                }
+                key
+            }
+        };
+        self.take_while(|c| c == ' ' || c == '\t');
+        Ok(key)
+    }
+
+    fn parse_toml_value(&mut self) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some('"') => Ok(Value::Str(self.parse_quoted('"', true)?)),
+            Some('\'') => Ok(Value::Str(self.parse_quoted('\'', false)?)),
+            Some('[') => {
+                self.next();
+                let mut elements = vec![];
+                loop {
+                    self.skip_toml_whitespace();
+                    if self.eat(']') {
+                        return Ok(Value::Array(elements));
+                    }
+                    elements.push(self.parse_toml_value()?);
+                    self.skip_toml_whitespace();
+                    if !self.eat(',') {
+                        self.skip_toml_whitespace();
+                        self.expect(']')?;
+                        return Ok(Value::Array(elements));
+                    }
+                }
+            }
+            Some('{') => Err(self.error("Inline tables are not supported.".to_string())),
+            Some(_) => {
+                let word = self.take_while(|c| {
+                    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+' || c == '.'
+                });
+                match word.as_str() {
+                    "" => Err(self.unexpected("a value")),
+                    "true" | "false" => Ok(Value::Literal(word)),
+                    _ if is_toml_number(&word) => {
+                        Ok(Value::Literal(word.trim_start_matches('+').to_string()))
+                    }
+                    _ => Err(self.error(format!("Unsupported value `{}`.", word))),
+                }
+            }
+            None => Err(self.unexpected("a value")),
+        }
+    }
+}
+
+// Integers and floats which are also valid rust literals. Dates, `inf`, and `nan` are not.
+fn is_toml_number(word: &str) -> bool {
+    let digits = word.trim_start_matches(['+', '-']);
+    if let Some(radix_digits) = ["0x", "0o", "0b"]
+        .iter()
+        .find_map(|prefix| digits.strip_prefix(prefix))
+    {
+        return !radix_digits.is_empty()
+            && radix_digits
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() || c == '_');
+    }
+    let has_exponent = digits.contains(['e', 'E']);
+    // Signs are only allowed in the exponent. Dates such as `1979-05-27` contain a `-`.
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || "_.eE+-".contains(c))
+        && (has_exponent || !digits.contains(['+', '-']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(rows: &[Row]) -> Vec<Vec<(&str, &Value)>> {
+        rows.iter()
+            .map(|row| {
+                row.columns
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_csv() {
+        let rows = parse_rows(
+            "csv",
+            "input, expected\n42,ok\n\n\"4,2\", \"say \"\"hi\"\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(rows[0].line, 2);
+        assert_eq!(rows[1].line, 4);
+        assert_eq!(
+            columns(&rows),
+            [
+                [
+                    ("input", &Value::Field("42".to_string())),
+                    ("expected", &Value::Field("ok".to_string()))
+                ],
+                [
+                    ("input", &Value::Field("4,2".to_string())),
+                    ("expected", &Value::Field("say \"hi\"".to_string()))
+                ],
+            ]
+        );
+        assert_eq!(
+            parse_rows("csv", "a,b\n1,2\n3\n").unwrap_err(),
+            ParseError {
+                line: 3,
+                message: "Expected 2 fields like the header, found 1.".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_json() {
+        let rows = parse_rows(
+            "json",
+            "[\n  {\"a\": -1.5e3, \"b\": \"x\\n\\u00e9\"},\n  {\"a\": null, \"b\": [true, 2]}\n]",
+        )
+        .unwrap();
+        assert_eq!(rows[1].line, 3);
+        assert_eq!(
+            columns(&rows),
+            [
+                [
+                    ("a", &Value::Literal("-1.5e3".to_string())),
+                    ("b", &Value::Str("x\n\u{e9}".to_string()))
+                ],
+                [
+                    ("a", &Value::Null),
+                    (
+                        "b",
+                        &Value::Array(vec![
+                            Value::Literal("true".to_string()),
+                            Value::Literal("2".to_string())
+                        ])
+                    )
+                ],
+            ]
+        );
+        assert_eq!(
+            parse_rows("json", "[{\"a\": 1},\n{\"a\": {}}]")
+                .unwrap_err()
+                .line,
+            2
+        );
+    }
+
+    #[test]
+    fn parses_toml() {
+        let rows = parse_rows(
+            "toml",
+            "# cases\n[[case]]\ninput = 'C:\\dir' # path\ncount = +1_000\n\n[[case]]\ninput = \"a\\tb\"\ncount = [1,\n  2,\n]\n",
+        )
+        .unwrap();
+        assert_eq!(rows[1].line, 6);
+        assert_eq!(
+            columns(&rows),
+            [
+                [
+                    ("input", &Value::Str("C:\\dir".to_string())),
+                    ("count", &Value::Literal("1_000".to_string()))
+                ],
+                [
+                    ("input", &Value::Str("a\tb".to_string())),
+                    (
+                        "count",
+                        &Value::Array(vec![
+                            Value::Literal("1".to_string()),
+                            Value::Literal("2".to_string())
+                        ])
+                    )
+                ],
+            ]
+        );
+        assert_eq!(
+            parse_rows("toml", "[[case]]\na = 1\nb = 1979-05-27\n").unwrap_err(),
+            ParseError {
+                line: 3,
+                message: "Unsupported value `1979-05-27`.".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Expected results of test cases such as `#[test_case(2, 3 => 5)]` or `#[test_case(x => matches Ok(_))]`.
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+
+mod kw {
+    syn::custom_keyword!(matches);
+}
+
+/// Arguments of a test case attribute. The expectation follows the arguments after `=>` and the
+/// description after `;`. The types of a generic test function are given with `type = u32`.
+pub struct TestCaseArgs {
+    pub args: Vec<syn::Expr>,
+    pub expected: Option<Expectation>,
+    pub description: Option<syn::LitStr>,
+    pub case_type: Option<syn::Type>,
+}
+
+impl Parse for TestCaseArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = vec![];
+        let mut expected = None;
+        let mut description = None;
+        let mut case_type = None;
+        while !input.is_empty() {
+            // `type` is a keyword and no expression
+            if input.peek(syn::Token![type]) && input.peek2(syn::Token![=]) {
+                let keyword = input.parse::<syn::Token![type]>()?;
+                input.parse::<syn::Token![=]>()?;
+                if case_type.is_some() {
+                    return Err(syn::Error::new(
+                        keyword.span,
+                        "Type can only be defined once.",
+                    ));
+                }
+                case_type = Some(input.parse()?);
+            } else if input.peek(syn::Token![=>]) {
+                input.parse::<syn::Token![=>]>()?;
+                expected = Some(input.parse()?);
+            } else if input.peek(syn::Token![;]) {
+                input.parse::<syn::Token![;]>()?;
+                description = Some(input.parse()?);
+            } else {
+                args.push(input.parse()?);
+            }
+            // The expectation and the description directly follow the last argument
+            if input.is_empty()
+                || (input.peek(syn::Token![=>]) && expected.is_none())
+                || (input.peek(syn::Token![;]) && description.is_none())
+            {
+                continue;
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(TestCaseArgs {
+            args,
+            expected,
+            description,
+            case_type,
+        })
+    }
+}
+
+/// Check of the return value of a test case.
+#[derive(Clone)]
+pub enum Expectation {
+    /// `=> 5` compares the return value with `assert_eq!`.
+    Value(syn::Expr),
+    /// `=> matches Ok(_)` matches the return value against the pattern and an optional guard.
+    Matches {
+        pat: syn::Pat,
+        guard: Option<(syn::Token![if], syn::Expr)>,
+    },
+}
+
+impl Parse for Expectation {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `matches!(..)` is an expression
+        if input.peek(kw::matches) && !input.peek2(syn::Token![!]) {
+            input.parse::<kw::matches>()?;
+            let pat = input.parse()?;
+            let guard = if input.peek(syn::Token![if]) {
+                Some((input.parse()?, input.parse()?))
+            } else {
+                None
+            };
+            Ok(Expectation::Matches { pat, guard })
+        } else {
+            Ok(Expectation::Value(input.parse()?))
+        }
+    }
+}
+
+impl Expectation {
+    pub fn span(&self) -> proc_macro2::Span {
+        match self {
+            Expectation::Value(expr) => expr.span(),
+            Expectation::Matches { pat, .. } => pat.span(),
+        }
+    }
+
+    /// Checks the value of `ntest_result`. A mismatch panics with the source of the arguments.
+    pub fn to_check(&self, args: &[syn::Expr]) -> TokenStream {
+        let arguments = describe_arguments(args);
+        match self {
+            Expectation::Value(expected) => quote_spanned! {expected.span()=>
+                assert_eq!(ntest_result, #expected, "test case arguments: {}", #arguments);
+            },
+            Expectation::Matches { pat, guard } => {
+                let guard = guard
+                    .as_ref()
+                    .map(|(if_token, condition)| quote! { #if_token #condition });
+                quote_spanned! {pat.span()=>
+                    match ntest_result {
+                        #pat #guard => {}
+                        ntest_result => panic!(
+                            "assertion failed: `{:?}` does not match `{}`\ntest case arguments: {}",
+                            ntest_result,
+                            stringify!(#pat #guard),
+                            #arguments
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Source of the arguments as a string literal such as `"2, vec![1, 2]"`
+fn describe_arguments(args: &[syn::Expr]) -> TokenStream {
+    let mut parts = vec![];
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            parts.push(quote! { ", " });
+        }
+        parts.push(quote! { stringify!(#arg) });
+    }
+    quote! { concat!(#(#parts),*) }
+}
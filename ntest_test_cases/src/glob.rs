@@ -0,0 +1,155 @@
+//! File name patterns such as `tests/fixtures/**/*.json` for `#[test_files]`.
+//!
+//! Segments of the pattern are separated by `/`. Within a segment `*` matches any characters, `?`
+//! matches a single character, and `[abc]`, `[a-z]`, or `[!abc]` match a character of a set. The
+//! segment `**` matches any number of directories.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Files which match the pattern relative to `root`, sorted by path. The files are returned together
+/// with the directory in which the search started. It is the part of the pattern before the first
+/// wildcard.
+pub fn find_files(root: &Path, pattern: &str) -> io::Result<(PathBuf, Vec<PathBuf>)> {
+    let segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+    // The last segment names the files even if it contains no wildcard
+    let literal_segments = segments
+        .iter()
+        .take(segments.len().saturating_sub(1))
+        .take_while(|segment| !has_wildcard(segment))
+        .count();
+    let base = segments[..literal_segments]
+        .iter()
+        .fold(root.to_path_buf(), |base, segment| base.join(segment));
+    let pattern = &segments[literal_segments..];
+    let mut files = vec![];
+    if base.is_dir() {
+        collect_files(&base, &mut vec![], pattern, &mut files)?;
+    }
+    files.sort();
+    Ok((base, files))
+}
+
+fn has_wildcard(segment: &str) -> bool {
+    segment.contains(['*', '?', '['])
+}
+
+// Walks the directory. Symbolic links to directories are not followed.
+fn collect_files(
+    dir: &Path,
+    relative: &mut Vec<String>,
+    pattern: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        relative.push(name);
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), relative, pattern, files)?;
+        } else if matches_path(pattern, relative) {
+            files.push(entry.path());
+        }
+        relative.pop();
+    }
+    Ok(())
+}
+
+fn matches_path(pattern: &[&str], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => {
+            matches_path(rest, path) || (!path.is_empty() && matches_path(pattern, &path[1..]))
+        }
+        Some((segment, rest)) => match path.split_first() {
+            Some((name, path)) => {
+                let segment: Vec<char> = segment.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                matches_segment(&segment, &name) && matches_path(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn matches_segment(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_segment(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_segment(rest, &name[1..]),
+        Some(('[', rest)) => match (rest.iter().position(|c| *c == ']'), name.split_first()) {
+            (Some(end), Some((c, name))) if end > 0 => {
+                let (negated, set) = match &rest[..end] {
+                    ['!', set @ ..] if !set.is_empty() => (true, set),
+                    set => (false, set),
+                };
+                in_set(set, *c) != negated && matches_segment(&rest[end + 1..], name)
+            }
+            // Brackets without a set such as `[]` are matched literally
+            _ => name.first() == Some(&'[') && matches_segment(rest, &name[1..]),
+        },
+        Some((c, rest)) => name.first() == Some(c) && matches_segment(rest, &name[1..]),
+    }
+}
+
+// Characters and ranges such as `a-z`
+fn in_set(set: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            if set[i] <= c && c <= set[i + 2] {
+                return true;
+            }
+            i += 3;
+        } else {
+            if set[i] == c {
+                return true;
+            }
+            i += 1;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        let path: Vec<String> = path.split('/').map(String::from).collect();
+        matches_path(&pattern, &path)
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("*.json", "a.json"));
+        assert!(matches("*.json", ".json"));
+        assert!(!matches("*.json", "a.json5"));
+        assert!(!matches("*.json", "dir/a.json"));
+        assert!(matches("**/*.json", "a.json"));
+        assert!(matches("**/*.json", "dir/sub/a.json"));
+        assert!(matches("dir/**/a.?son", "dir/x/y/a.json"));
+        assert!(!matches("dir/**/a.?son", "other/a.json"));
+        assert!(matches("case_[0-9][!a].txt", "case_7b.txt"));
+        assert!(!matches("case_[0-9][!a].txt", "case_7a.txt"));
+        assert!(!matches("case_[0-9].txt", "case_x.txt"));
+        assert!(matches("[]].txt", "[]].txt"));
+    }
+
+    #[test]
+    fn finds_files_below_literal_directories() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let (base, files) = find_files(root, "tests/data/*.csv").unwrap();
+        assert_eq!(base, root.join("tests").join("data"));
+        assert_eq!(files, [base.join("invalid.csv"), base.join("parse.csv"),]);
+        let (base, files) = find_files(root, "./tests/data/parse.csv").unwrap();
+        assert_eq!(files, [base.join("parse.csv")]);
+        assert!(find_files(root, "missing/*.csv").unwrap().1.is_empty());
+    }
+}
@@ -3,23 +3,42 @@
 extern crate proc_macro;
 extern crate syn;
 
+use ntest_proc_macro_helper::{check_errors, combine_errors, ntest_path, path_ends_with};
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::quote;
-use syn::parse_macro_input;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+mod data_file;
+mod expectation;
+mod glob;
+mod matrix;
+mod naming;
+mod pairwise;
 mod syn_helper;
 
+use expectation::{Expectation, TestCaseArgs};
+
 /// Test cases can be used to have multiple inputs for a given function.
 /// With the `#[test_case]` attribute multiple tests will be generated using the
 /// [Procedural Macros](https://blog.rust-lang.org/2018/12/21/Procedural-Macros-in-Rust-2018.html)
 /// capabilities of rust.
 ///
-/// The function input can be of type `int`, `bool`, or `str`, or a path to an
-/// enum or constant of those types.
+/// The arguments can be any rust expression such as literals, paths to an enum or constant, tuples,
+/// arrays, `vec![]`, struct literals, method calls, or closures.
 ///
 /// Please note that rust functions can only contain alphanumeric characters and '_' signs.
 /// Special characters will be escaped using a meaningful replacement (for example `#` will be replaced with `_hash`),
-/// or as a default the '_' sign.
+/// or as a default the '_' sign. Letters of other scripts are transliterated such as `gruesse` for
+/// `"grüße"`, other characters are named by their code point such as `_u65e5` for `日`. Test names of
+/// other expressions are derived from the identifiers, literals, and operators they contain. For
+/// example `Duration::from_millis(5)` is named `Duration_from_millis_5`. Names longer than 64
+/// characters are cut and end with a hash of the full name.
+///
+/// Test cases whose derived names collide are named after the full paths of their arguments, for
+/// example `colors_RED` and `fruits_RED` for `colors::RED` and `fruits::RED`. Otherwise they are
+/// numbered such as `test_a_b` and `test_a_b_2`. Test cases with the same arguments or the same
+/// explicit name are a compile error which lists all clashing names.
 ///
 /// A function annotated with a `#[test_case]` attribute will be split into multiple rust functions annotated with the `#[test]` attribute.
 ///
@@ -59,6 +78,32 @@ mod syn_helper;
 /// }
 /// ```
 ///
+/// Example with expressions:
+/// ```ignore
+/// #[test_case(vec![1, 2], (0, Some(3)))]
+/// #[test_case(vec![0; 4], (Duration::from_millis(5).as_millis() as usize, None))]
+/// fn expressions(x: Vec<u32>, y: (usize, Option<u32>)) {
+/// }
+/// ```
+///
+/// The return value of the function can be checked with an expected result after `=>`. It is
+/// either compared with `assert_eq!` or matched against a pattern with `matches`. A mismatch prints
+/// the arguments of the test case:
+/// ```ignore
+/// #[test_case(2, 3 => 5)]
+/// #[test_case(-1, 1 => 0)]
+/// fn add(a: i32, b: i32) -> i32 {
+///     a + b
+/// }
+///
+/// #[test_case("42" => matches Ok(42))]
+/// #[test_case("4a" => matches Err(_))]
+/// #[test_case("49" => matches Ok(x) if x % 7 == 0)]
+/// fn parse(input: &str) -> Result<u32, ParseIntError> {
+///     input.parse()
+/// }
+/// ```
+///
 /// Example with name attribute:
 /// ```ignore
 /// #[test_case(42, name="my_fancy_test")]
@@ -67,6 +112,15 @@ mod syn_helper;
 /// }
 /// ```
 ///
+/// A name containing parameters in braces is a template. The parameters are replaced with the names
+/// of the arguments, in this example `parse_42_as_Int`. `{{` and `}}` are literal braces:
+/// ```ignore
+/// #[test_case("42", Kind::Int, name = "parse_{input}_as_{kind}")]
+/// fn parse(input: &str, kind: Kind) {
+///     assert!(parse_as(input, kind).is_ok());
+/// }
+/// ```
+///
 /// Example with rust test attributes.
 /// All attributes after a test case will be appended after the generated `#[test]` attribute.
 /// For example the following test cases...
@@ -99,6 +153,41 @@ mod syn_helper;
 /// }
 /// ```
 ///
+/// The `panics` and `ignore` keywords are short forms of these attributes for a single test case.
+/// A description after `;` names the test case, in this example `is_prime_three_is_prime`:
+///
+/// ```ignore
+/// #[test_case(3; "three is prime")]
+/// #[test_case(0, panics = "not defined for zero")]
+/// #[test_case(7919, ignore = "too slow")]
+/// fn is_prime(x: u32) {
+///     assert!(check_prime(x));
+/// }
+/// ```
+///
+/// A timeout can be given for a single test case with the `timeout` keyword. It takes an integer in
+/// milliseconds or a duration string like the `#[timeout]` attribute. The `#[test_case_timeout]`
+/// attribute sets a default timeout for all test cases of the function which do not define their own:
+///
+/// ```ignore
+/// #[test_case(10)]
+/// #[test_case(500, timeout = "2s")]
+/// #[test_case_timeout(100)]
+/// fn sleeps(ms: u64) {
+///     thread::sleep(Duration::from_millis(ms));
+/// }
+/// ```
+///
+/// Test cases of async functions need the test attribute of an async runtime. It is set with the
+/// `test_attr` keyword or the [macro@test_case_attr] attribute:
+///
+/// ```ignore
+/// #[test_case(1, test_attr = tokio::test)]
+/// async fn fetches(id: u32) {
+///     assert!(fetch(id).await.is_ok());
+/// }
+/// ```
+///
 /// Test functions with a `Result` return are also supported:
 ///
 /// ```ignore
@@ -110,193 +199,1272 @@ mod syn_helper;
 /// ```
 #[proc_macro_attribute]
 pub fn test_case(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as syn::ItemFn);
-    let attribute_args = parse_macro_input!(attr as syn::AttributeArgs);
+    expand_test_case(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generates a test case for every combination of the given arrays of values.
+///
+/// Every array holds the values of one function parameter. The test cases are named like the
+/// test cases of [macro@test_case]. A test matrix can be combined with further test matrices and test
+/// cases on the same function. Attributes after a test matrix apply to all of its test cases:
+///
+/// ```ignore
+/// #[test_matrix([1, 2, 4], ["utf8", "latin1"], [true, false])]
+/// #[test_case(0, "ascii", false)]
+/// #[should_panic]
+/// fn encode(size: usize, encoding: &str, strict: bool) {
+///     assert!(size > 0);
+/// }
+/// ```
+///
+/// The example generates 13 tests such as `encode_1_utf8_true` and `encode_4_latin1_false`. Test
+/// cases with the same arguments are a compile error.
+#[proc_macro_attribute]
+pub fn test_matrix(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_test_matrix(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generates test cases which cover every pair of values of the given arrays.
+///
+/// Like for [macro@test_matrix] every array holds the values of one function parameter. Instead of
+/// all combinations only as many test cases are generated as needed to test every value of each
+/// parameter together with every value of each other parameter. With `strength = 3` every
+/// combination of values of three parameters is covered. The test cases are computed at compile
+/// time and do not change between builds:
+///
+/// ```ignore
+/// #[test_pairwise(["ext4", "btrfs", "xfs"], [512, 4096], [true, false], [1, 2, 4, 8])]
+/// fn mount(fs: &str, block_size: u32, journal: bool, threads: u32) {
+///     // 12 test cases instead of 48 for all combinations
+/// }
+///
+/// #[test_pairwise([1, 2, 3], [4, 5, 6], [7, 8, 9], [10, 11, 12], strength = 3)]
+/// fn triples(a: u32, b: u32, c: u32, d: u32) {
+///     // 34 test cases instead of 81 for all combinations
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test_pairwise(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_test_pairwise(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Instantiates a generic test function for each of the given types.
+///
+/// Every test case of the function is generated once per type and named after the type. Without
+/// further test cases a single test per type is generated. A test case can also give its own type
+/// with the `type` keyword. Functions with several type parameters take a tuple of types per
+/// instantiation such as `(u8, String)`:
+///
+/// ```ignore
+/// #[test_case_types(u8, u16, MyInt)]
+/// fn roundtrip<T: Codec + Default + PartialEq>() {
+///     assert!(T::decode(&T::default().encode()) == T::default());
+/// }
+///
+/// #[test_case(5, type = u32)]
+/// #[test_case(-5, type = i64)]
+/// fn parses<T: FromStr + Display>(x: T) {
+///     assert_eq!(x.to_string().parse::<T>().unwrap().to_string(), x.to_string());
+/// }
+/// ```
+///
+/// The example generates the tests `roundtrip_u8`, `roundtrip_u16`, `roundtrip_MyInt`, `parses_5_u32`,
+/// and `parses_n5_i64`. Within the test the type parameter is an alias of the concrete type.
+#[proc_macro_attribute]
+pub fn test_case_types(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_test_case_types(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Sets the default timeout for all test cases of a function which do not define their own timeout.
+///
+/// The timeout is given as integer in milliseconds or as duration string like for the `#[timeout]`
+/// attribute. See [macro@test_case] for an example.
+#[proc_macro_attribute]
+pub fn test_case_timeout(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_test_case_timeout(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Sets the test attribute of all test cases of a function which do not define their own.
+///
+/// By default test cases are annotated with `#[test]`. Async test functions need the test attribute
+/// of their runtime instead, for example `#[tokio::test]`. It is given for all test cases with
+/// `#[test_case_attr]` or for a single test case with the `test_attr` keyword:
+///
+/// ```ignore
+/// #[test_case(1)]
+/// #[test_case(2, test_attr = tokio::test(flavor = "current_thread"))]
+/// #[test_case_attr(tokio::test(flavor = "multi_thread"))]
+/// async fn fetches(id: u32) {
+///     assert!(fetch(id).await.is_ok());
+/// }
+/// ```
+///
+/// If the `tokio` or `async-std` feature of `ntest` is enabled, async test functions without a test
+/// attribute get `#[tokio::test]` or `#[async_std::test]`. The crate of the runtime must be a
+/// dependency of the tested crate with its test attribute enabled. Smol has no test attribute. With
+/// the `smol` feature such test cases are plain tests which run their body with `smol::block_on`.
+#[proc_macro_attribute]
+pub fn test_case_attr(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_test_case_attr(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generates a test for every row of a CSV, JSON, or TOML file.
+///
+/// The path is relative to the directory of the `Cargo.toml` of the crate. The columns of a row are
+/// passed to the function parameters of the same name. The test is named after the column given with
+/// `name`, or after the index of the row. Changes of the file trigger a rebuild of the tests.
+///
+/// The supported formats describe a flat table:
+/// - CSV: a header line with the column names followed by one line per row. Fields of `&str` and
+///   `String` parameters are strings, other fields are rust expressions such as `42` or `Some(1)`.
+/// - JSON: an array of objects. Every object is a row.
+/// - TOML: an array of tables such as `[[case]]`. Every table is a row.
+///
+/// Empty CSV fields and JSON `null` values are `None` for `Option` parameters, arrays are `vec![]`.
+///
+/// ```ignore
+/// // tests/data/parse.csv:
+/// // input,expected
+/// // 42,Ok(42)
+/// // 4a,Err(())
+/// #[test_cases_from("tests/data/parse.csv", name = "input")]
+/// fn parse(input: &str, expected: Result<u32, ()>) {
+///     assert_eq!(input.parse::<u32>().map_err(|_| ()), expected);
+/// }
+/// ```
+///
+/// The example generates the tests `parse_42` and `parse_4a`. A row which does not match the
+/// function signature is a compile error which points at the line of the file.
+#[proc_macro_attribute]
+pub fn test_cases_from(attr: TokenStream, item: TokenStream) -> TokenStream {
+    data_file::expand_test_cases_from(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Generates a test for every file which matches a pattern such as `tests/fixtures/**/*.json`.
+///
+/// The pattern is relative to the directory of the `Cargo.toml` of the crate. Within a directory or
+/// file name `*` matches any characters, `?` a single character, and `[a-z]` a character of a set.
+/// The directory `**` matches any number of directories. The function takes the path of the file as
+/// `path: &Path` and optionally its content as `contents: &str` or `contents: &[u8]`. The tests are
+/// named after the path of the file relative to the directory before the first wildcard.
+///
+/// ```ignore
+/// #[test_files("tests/fixtures/**/*.json")]
+/// fn parses_fixture(path: &Path, contents: &str) {
+///     assert!(parse(contents).is_ok(), "{}", path.display());
+/// }
+/// ```
+///
+/// For the file `tests/fixtures/user/empty.json` the example generates the test
+/// `parses_fixture_user_slashempty_full_stopjson`. Changes of the matched files trigger a rebuild.
+/// Added or removed files do not, since stable Rust offers no way for an attribute to depend on a
+/// directory. To pick them up, a build script of the crate has to watch the directory:
+///
+/// ```ignore
+/// // build.rs
+/// fn main() {
+///     println!("cargo:rerun-if-changed=tests/fixtures");
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test_files(attr: TokenStream, item: TokenStream) -> TokenStream {
+    data_file::expand_test_files(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// The attribute is only expanded if it is placed above the first test case. It is moved below the
+// test cases where the test case attribute reads it.
+fn expand_test_case_timeout(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut input = syn::parse2::<syn::ItemFn>(item)?;
+    if !input
+        .attrs
+        .iter()
+        .any(|attribute| CaseKind::of(&attribute.path).is_some())
+    {
+        return Err(syn::Error::new_spanned(
+            &input.sig,
+            "The test case timeout attribute is only allowed on functions with test cases.",
+        ));
+    }
+    input
+        .attrs
+        .push(syn::parse_quote! { #[test_case_timeout(#attr)] });
+    Ok(quote! { #input })
+}
+
+// Like the test case timeout attribute it is moved below the test cases. A function with test case
+// types but without test cases is expanded by the test case types attribute.
+fn expand_test_case_attr(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut input = syn::parse2::<syn::ItemFn>(item)?;
+    if !input.attrs.iter().any(|attribute| {
+        CaseKind::of(&attribute.path).is_some()
+            || path_ends_with(&attribute.path, "test_case_types")
+    }) {
+        return Err(syn::Error::new_spanned(
+            &input.sig,
+            "The test case attr attribute is only allowed on functions with test cases.",
+        ));
+    }
+    input
+        .attrs
+        .push(syn::parse_quote! { #[test_case_attr(#attr)] });
+    Ok(quote! { #input })
+}
+
+// Above test cases the attribute is moved below them where the test case attribute reads it.
+// Without test cases it generates a single test per type.
+fn expand_test_case_types(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut input = syn::parse2::<syn::ItemFn>(item)?;
+    let has_test_cases = input
+        .attrs
+        .iter()
+        .any(|attribute| CaseKind::of(&attribute.path).is_some());
+    input
+        .attrs
+        .push(syn::parse_quote! { #[test_case_types(#attr)] });
+    if has_test_cases {
+        Ok(quote! { #input })
+    } else {
+        expand_test_cases(
+            CaseKind::TestCase,
+            proc_macro2::TokenStream::new(),
+            quote! { #input },
+        )
+    }
+}
+
+fn expand_test_case(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    expand_test_cases(CaseKind::TestCase, attr, item)
+}
+
+fn expand_test_matrix(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    expand_test_cases(CaseKind::Matrix, attr, item)
+}
+
+fn expand_test_pairwise(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    expand_test_cases(CaseKind::Pairwise, attr, item)
+}
+
+// The outermost test case or test matrix attribute expands all test cases of the function
+fn expand_test_cases(
+    kind: CaseKind,
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut errors = vec![];
+    let input = match syn::parse2::<syn::ItemFn>(item) {
+        Ok(input) => input,
+        Err(e) => {
+            errors.push(e);
+            return Err(combine_errors(errors));
+        }
+    };
 
     let test_descriptions: Vec<TestDescription> =
-        collect_test_descriptions(&input, &attribute_args);
+        collect_test_descriptions(&input, kind, attr, &mut errors);
+    let test_descriptions = instantiate_types(&input, test_descriptions, &mut errors);
+    let type_params: Vec<&syn::Ident> = input
+        .sig
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
     let fn_body = &input.block;
-    let (fn_args_idents, fn_args_ty  ) = collect_function_arg_idents(&input);
+    let (fn_args_idents, fn_args_ty) = collect_function_arg_idents(&input, &mut errors);
     let fn_return = &input.sig.output;
+    let asyncness = &input.sig.asyncness;
+    // The argument count is only meaningful if all parameters are supported. All test cases of a
+    // matrix have the same count.
+    if fn_args_idents.len() == input.sig.inputs.len() {
+        let mut previous_group = None;
+        for test_description in &test_descriptions {
+            if previous_group.replace(test_description.group) != Some(test_description.group) {
+                check_argument_count(test_description, &fn_args_idents, &mut errors);
+            }
+        }
+    }
+    let test_descriptions = naming::assign_unique_names(test_descriptions, &mut errors);
+    let default_timeout = collect_default_timeout(&input, &mut errors);
+    let default_test_attr = collect_default_test_attr(&input, &mut errors).or_else(|| {
+        input
+            .sig
+            .asyncness
+            .as_ref()
+            .and_then(|_| runtime_test_attr())
+    });
+    if let Some(asyncness) = &input.sig.asyncness {
+        if default_test_attr.is_none()
+            && !cfg!(feature = "smol")
+            && test_descriptions
+                .iter()
+                .any(|test_description| test_description.test_attr.is_none())
+        {
+            errors.push(syn::Error::new_spanned(
+                asyncness,
+                "Async test cases need the test attribute of an async runtime such as #[test_case_attr(tokio::test)], or one of the `tokio`, `async-std`, or `smol` features.",
+            ));
+        }
+    }
+    if let syn::ReturnType::Default = fn_return {
+        for expected in test_descriptions
+            .iter()
+            .filter_map(|test_description| test_description.expected.as_ref())
+        {
+            errors.push(syn::Error::new(
+                expected.span(),
+                "An expected result requires a test function which returns a value.",
+            ));
+        }
+    }
+    check_errors(errors)?;
 
     let mut result = proc_macro2::TokenStream::new();
     for test_description in test_descriptions {
         let test_case_name = syn::Ident::new(&test_description.name, Span::call_site());
         let literals = test_description.args;
         let attributes = test_description.attributes;
-        if literals.len() != fn_args_idents.len() {
-            panic!("Test case arguments and function input signature mismatch.");
-        }
+        // A `#[timeout]` attribute after the test case replaces the default timeout
+        let timeout = test_description.timeout.or_else(|| {
+            default_timeout.clone().filter(|_| {
+                !attributes
+                    .iter()
+                    .any(|attribute| path_ends_with(&attribute.path, "timeout"))
+            })
+        });
+        let timeout = timeout
+            .map(|timeout| ntest_path().map(|ntest| quote! { #[#ntest::timeout(#timeout)] }))
+            .transpose()?;
+        let test_attr = test_description
+            .test_attr
+            .or_else(|| default_test_attr.clone());
+        // Without a test attribute async test cases of the smol feature block on their body
+        let block_on = asyncness.is_some() && test_attr.is_none() && cfg!(feature = "smol");
+        let test_attr = test_attr.unwrap_or_else(|| syn::parse_quote! { test });
+        // The type parameters are aliases of the types of the test case
+        let types = test_description
+            .case_type
+            .as_ref()
+            .and_then(|case_type| type_arguments(case_type, type_params.len()))
+            .unwrap_or_default();
 
-        let test_case_quote = quote! {
-            #[test]
+        let (test_return, test_body) = match &test_description.expected {
+            None => (
+                quote! { #fn_return },
+                quote! {
+                    #(type #type_params = #types;)*
+                    #(let #fn_args_idents: #fn_args_ty = #literals;)*
+                    #fn_body
+                },
+            ),
+            Some(expected) => {
+                // The body runs in a closure so that `return` and `?` yield the result
+                let closure_return = match fn_return {
+                    syn::ReturnType::Type(_, ty) if !matches!(**ty, syn::Type::ImplTrait(_)) => {
+                        quote! { -> #ty }
+                    }
+                    _ => quote! {},
+                };
+                let check = expected.to_check(&literals);
+                // An async block has no return type. It is inferred from the type of the result.
+                let result = match asyncness {
+                    Some(_) => match fn_return {
+                        syn::ReturnType::Type(_, ty)
+                            if !matches!(**ty, syn::Type::ImplTrait(_)) =>
+                        {
+                            quote! { let ntest_result: #ty = async move #fn_body.await; }
+                        }
+                        _ => quote! { let ntest_result = async move #fn_body.await; },
+                    },
+                    None => quote! { let ntest_result = (move || #closure_return #fn_body)(); },
+                };
+                (
+                    quote! {},
+                    quote! {
+                        #(type #type_params = #types;)*
+                        #(let #fn_args_idents: #fn_args_ty = #literals;)*
+                        #result
+                        #check
+                    },
+                )
+            }
+        };
+        let (test_asyncness, test_body) = if block_on {
+            (None, quote! { ::smol::block_on(async move { #test_body }) })
+        } else {
+            (asyncness.as_ref(), test_body)
+        };
+        result.extend(quote! {
+            #[#test_attr]
             #[allow(non_snake_case)]
             #(#attributes)*
-            fn #test_case_name() #fn_return {
-                #(let #fn_args_idents: #fn_args_ty = #literals;)*
-                #fn_body
+            #timeout
+            #test_asyncness fn #test_case_name() #test_return {
+                #test_body
             }
-        };
-        result.extend(test_case_quote);
+        });
+    }
+    Ok(result)
+}
+
+// Points at the surplus argument or at the test case which lacks arguments
+fn check_argument_count(
+    test_description: &TestDescription,
+    fn_args_idents: &[syn::Ident],
+    errors: &mut Vec<syn::Error>,
+) {
+    let args = &test_description.args;
+    if args.len() > fn_args_idents.len() {
+        for surplus in &args[fn_args_idents.len()..] {
+            errors.push(syn::Error::new_spanned(
+                surplus,
+                format!(
+                    "Test case arguments and function input signature mismatch. Expected {} argument(s), found {}.",
+                    fn_args_idents.len(),
+                    args.len()
+                ),
+            ));
+        }
+    } else if args.len() < fn_args_idents.len() {
+        let missing: Vec<String> = fn_args_idents[args.len()..]
+            .iter()
+            .map(|ident| format!("`{}`", ident))
+            .collect();
+        errors.push(syn::Error::new(
+            test_description.span,
+            format!(
+                "Test case arguments and function input signature mismatch. Missing argument(s) for {}.",
+                missing.join(", ")
+            ),
+        ));
     }
-    result.into()
 }
 
-fn collect_function_arg_idents(input: &syn::ItemFn) -> (Vec<syn::Ident>, Vec<syn::Type>) {
+fn collect_function_arg_idents(
+    input: &syn::ItemFn,
+    errors: &mut Vec<syn::Error>,
+) -> (Vec<syn::Ident>, Vec<syn::Type>) {
     let mut fn_args_idents: Vec<syn::Ident> = vec![];
     let mut fn_types: Vec<syn::Type> = vec![];
     let fn_args = &input.sig.inputs;
     for i in fn_args {
         match i {
-            syn::FnArg::Typed(t) => {
-                let ubox_t = *(t.pat.clone());
-                match ubox_t {
-                    syn::Pat::Ident(i) => {
-                        fn_args_idents.push(i.ident.clone());
-                    }
-                    _ => panic!("Unexpected function identifier."),
+            syn::FnArg::Typed(t) => match &*t.pat {
+                syn::Pat::Ident(i) => {
+                    fn_args_idents.push(i.ident.clone());
+                    fn_types.push(*t.ty.clone());
                 }
-                fn_types.push(*t.ty.clone());
-            }
-            syn::FnArg::Receiver(_) => {
-                panic!("Receiver function not expected for test case attribute.")
-            }
+                pat => errors.push(syn::Error::new_spanned(
+                    pat,
+                    "Unexpected function identifier. Test case parameters must be plain identifiers.",
+                )),
+            },
+            syn::FnArg::Receiver(receiver) => errors.push(syn::Error::new_spanned(
+                receiver,
+                "Receiver function not expected for test case attribute.",
+            )),
         }
     }
     (fn_args_idents, fn_types)
 }
 
+#[derive(Clone)]
 struct TestDescription {
+    // Name with the full paths of the arguments for derived names. Explicit names have none.
+    qualified_name: Option<String>,
     args: Vec<syn::Expr>,
+    expected: Option<Expectation>,
     name: String,
+    timeout: Option<syn::Lit>,
     attributes: Vec<syn::Attribute>,
+    // Span of the test case attribute
+    span: Span,
+    // Index of the attribute. All test cases of a matrix are in the same group.
+    group: usize,
+    // Types of the type parameters of a generic function
+    case_type: Option<syn::Type>,
+    // Test attribute of the `test_attr` keyword which replaces `#[test]`
+    test_attr: Option<syn::Meta>,
+}
+
+// Attributes which define test cases
+#[derive(Clone, Copy, PartialEq)]
+enum CaseKind {
+    TestCase,
+    Matrix,
+    Pairwise,
+}
+
+impl CaseKind {
+    fn of(path: &syn::Path) -> Option<Self> {
+        if path_ends_with(path, "test_case") {
+            Some(CaseKind::TestCase)
+        } else if path_ends_with(path, "test_matrix") {
+            Some(CaseKind::Matrix)
+        } else if path_ends_with(path, "test_pairwise") {
+            Some(CaseKind::Pairwise)
+        } else {
+            None
+        }
+    }
+
+    fn missing_arguments_message(self) -> &'static str {
+        match self {
+            CaseKind::TestCase => {
+                "Test case attributes need at least one argument such as #[test_case(42)]."
+            }
+            CaseKind::Matrix => "Test matrix attributes need at least one array of values such as #[test_matrix([1, 2], [true, false])].",
+            CaseKind::Pairwise => "Test pairwise attributes need at least one array of values such as #[test_pairwise([1, 2], [true, false])].",
+        }
+    }
 }
 
 fn collect_test_descriptions(
     input: &syn::ItemFn,
-    attribute_args: &syn::AttributeArgs,
+    kind: CaseKind,
+    attr: proc_macro2::TokenStream,
+    errors: &mut Vec<syn::Error>,
 ) -> Vec<TestDescription> {
     let mut test_case_descriptions: Vec<TestDescription> = vec![];
 
     let fn_name = input.sig.ident.to_string();
-    let test_case_parameter = parse_test_case_attributes(attribute_args);
-    let test_name = calculate_test_name(&test_case_parameter, &fn_name);
-    let curr_test_attributes = TestDescription {
-        args: test_case_parameter.args,
-        name: test_name,
-        attributes: vec![],
+    // Parameter names for the placeholders of name templates
+    let params: Vec<String> = input
+        .sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            syn::FnArg::Typed(syn::PatType { pat, .. }) => match &**pat {
+                syn::Pat::Ident(pat) => syn::ext::IdentExt::unraw(&pat.ident).to_string(),
+                _ => String::new(),
+            },
+            syn::FnArg::Receiver(_) => String::new(),
+        })
+        .collect();
+    let names = naming::TestNaming {
+        fn_name: &fn_name,
+        params: &params,
     };
-    test_case_descriptions.push(curr_test_attributes);
-    for attribute in &input.attrs {
-        let meta = attribute.parse_meta();
-        match meta {
-            Ok(m) => match m {
-                syn::Meta::Path(p) => {
-                    let identifier = p.get_ident().expect("Expected identifier!");
-                    if identifier == "test_case" {
-                        panic!("Test case attributes need at least one argument such as #[test_case(42)].");
-                    } else {
-                        test_case_descriptions
-                            .last_mut()
-                            .unwrap()
-                            .attributes
-                            .push(attribute.clone());
-                    }
+    test_case_descriptions.extend(describe_test_cases(
+        kind,
+        attr,
+        Span::call_site(),
+        0,
+        &names,
+        errors,
+    ));
+    // Test cases of the last test case or matrix attribute. Following attributes apply to them.
+    let mut last_cases = 0..test_case_descriptions.len();
+    for (index, attribute) in input
+        .attrs
+        .iter()
+        .filter(|attribute| {
+            !path_ends_with(&attribute.path, "test_case_timeout")
+                && !path_ends_with(&attribute.path, "test_case_types")
+                && !path_ends_with(&attribute.path, "test_case_attr")
+        })
+        .enumerate()
+    {
+        let kind = match CaseKind::of(&attribute.path) {
+            Some(kind) => kind,
+            None => {
+                for test_description in &mut test_case_descriptions[last_cases.clone()] {
+                    test_description.attributes.push(attribute.clone());
                 }
-                syn::Meta::List(ml) => {
-                    let identifier = ml.path.get_ident().expect("Expected identifier!");
-                    if identifier == "test_case" {
-                        let argument_args: syn::AttributeArgs = ml.nested.into_iter().collect();
-                        let test_case_parameter = parse_test_case_attributes(&argument_args);
-                        let test_name = calculate_test_name(&test_case_parameter, &fn_name);
-                        let curr_test_attributes = TestDescription {
-                            args: test_case_parameter.args,
-                            name: test_name,
-                            attributes: vec![],
-                        };
-                        test_case_descriptions.push(curr_test_attributes);
-                    } else {
-                        test_case_descriptions
-                            .last_mut()
-                            .unwrap()
-                            .attributes
-                            .push(attribute.clone());
-                    }
+                continue;
+            }
+        };
+        if attribute.tokens.is_empty() {
+            errors.push(syn::Error::new_spanned(
+                attribute,
+                kind.missing_arguments_message(),
+            ));
+            continue;
+        }
+        match attribute.parse_args::<proc_macro2::TokenStream>() {
+            Ok(args) => {
+                let descriptions =
+                    describe_test_cases(kind, args, attribute.span(), index + 1, &names, errors);
+                if !descriptions.is_empty() {
+                    last_cases = test_case_descriptions.len()
+                        ..test_case_descriptions.len() + descriptions.len();
+                    test_case_descriptions.extend(descriptions);
                 }
-                syn::Meta::NameValue(_) => {
-                    test_case_descriptions
-                        .last_mut()
-                        .unwrap()
-                        .attributes
-                        .push(attribute.clone());
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    test_case_descriptions
+}
+
+// Test cases of a single test case or matrix attribute
+fn describe_test_cases(
+    kind: CaseKind,
+    args: proc_macro2::TokenStream,
+    span: Span,
+    group: usize,
+    names: &naming::TestNaming,
+    errors: &mut Vec<syn::Error>,
+) -> Vec<TestDescription> {
+    let cases = match kind {
+        CaseKind::TestCase => match syn::parse2::<TestCaseArgs>(args) {
+            Ok(args) => vec![parse_test_case_attributes(args, errors)],
+            Err(e) => {
+                errors.push(e);
+                vec![]
+            }
+        },
+        CaseKind::Matrix | CaseKind::Pairwise => {
+            matrix::parse_test_matrix(kind, args, span, errors)
+        }
+    };
+    cases
+        .into_iter()
+        .map(|test_case_parameter| {
+            let (name, qualified_name) =
+                naming::calculate_test_name(&test_case_parameter, names, errors);
+            TestDescription {
+                name,
+                qualified_name,
+                args: test_case_parameter.args,
+                expected: test_case_parameter.expected,
+                timeout: test_case_parameter.timeout,
+                attributes: test_case_parameter.attributes,
+                span,
+                group,
+                case_type: test_case_parameter.case_type,
+                test_attr: test_case_parameter.test_attr,
+            }
+        })
+        .collect()
+}
+
+// Repeats every test case without its own type for every type of the test case types attribute
+fn instantiate_types(
+    input: &syn::ItemFn,
+    test_descriptions: Vec<TestDescription>,
+    errors: &mut Vec<syn::Error>,
+) -> Vec<TestDescription> {
+    for param in &input.sig.generics.params {
+        if !matches!(param, syn::GenericParam::Type(_)) {
+            errors.push(syn::Error::new_spanned(
+                param,
+                "Only type parameters are supported for generic test cases.",
+            ));
+        }
+    }
+    let type_count = input.sig.generics.type_params().count();
+    let function_types = collect_case_types(input, errors);
+    for case_type in function_types.iter().flatten() {
+        check_case_type(case_type, type_count, errors);
+    }
+    let mut instances = vec![];
+    for test_description in test_descriptions {
+        match (&test_description.case_type, &function_types) {
+            (Some(case_type), _) => {
+                check_case_type(case_type, type_count, errors);
+                instances.push(test_description);
+            }
+            (None, Some(function_types)) => {
+                for case_type in function_types {
+                    let mut instance = test_description.clone();
+                    let type_name = syn_helper::type_to_identifier(case_type);
+                    instance.name = format!("{}_{}", instance.name, type_name);
+                    if let Some(qualified_name) = &instance.qualified_name {
+                        instance.name = naming::cap_length(instance.name);
+                        instance.qualified_name = Some(naming::cap_length(format!(
+                            "{}_{}",
+                            qualified_name, type_name
+                        )));
+                    }
+                    instance.case_type = Some(case_type.clone());
+                    instances.push(instance);
                 }
+            }
+            (None, None) if type_count > 0 => errors.push(syn::Error::new(
+                test_description.span,
+                "Generic test functions need the types of their test cases such as #[test_case_types(u8, u16)] or #[test_case(5, type = u32)].",
+            )),
+            (None, None) => instances.push(test_description),
+        }
+    }
+    instances
+}
+
+fn check_case_type(case_type: &syn::Type, type_count: usize, errors: &mut Vec<syn::Error>) {
+    if type_count == 0 {
+        errors.push(syn::Error::new_spanned(
+            case_type,
+            "Test case types require a generic test function such as `fn test<T>()`.",
+        ));
+    } else if type_arguments(case_type, type_count).is_none() {
+        errors.push(syn::Error::new_spanned(
+            case_type,
+            format!(
+                "Expected a tuple of {} types for the type parameters such as `(u8, String)`.",
+                type_count
+            ),
+        ));
+    }
+}
+
+fn collect_case_types(input: &syn::ItemFn, errors: &mut Vec<syn::Error>) -> Option<Vec<syn::Type>> {
+    let mut case_types: Option<Vec<syn::Type>> = None;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| path_ends_with(&attribute.path, "test_case_types"))
+    {
+        if case_types.is_some() {
+            errors.push(syn::Error::new_spanned(
+                attribute,
+                "Test case types attribute is only allowed once.",
+            ));
+            continue;
+        }
+        match attribute.parse_args_with(Punctuated::<syn::Type, syn::Token![,]>::parse_terminated)
+        {
+            Ok(types) if !types.is_empty() => case_types = Some(types.into_iter().collect()),
+            Ok(_) => errors.push(syn::Error::new_spanned(
+                attribute,
+                "Test case types attributes need at least one type such as #[test_case_types(u8, u16)].",
+            )),
+            Err(e) => errors.push(e),
+        }
+    }
+    case_types
+}
+
+// Types of the type parameters. Several type parameters take a tuple of types.
+fn type_arguments(case_type: &syn::Type, count: usize) -> Option<Vec<syn::Type>> {
+    match case_type {
+        _ if count == 1 => Some(vec![case_type.clone()]),
+        syn::Type::Tuple(tuple) if tuple.elems.len() == count => {
+            Some(tuple.elems.iter().cloned().collect())
+        }
+        syn::Type::Paren(paren) => type_arguments(&paren.elem, count),
+        _ => None,
+    }
+}
+
+// Test attribute of the `#[test_case_attr]` attribute which applies to all test cases of the function
+fn collect_default_test_attr(
+    input: &syn::ItemFn,
+    errors: &mut Vec<syn::Error>,
+) -> Option<syn::Meta> {
+    let mut default_test_attr = None;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| path_ends_with(&attribute.path, "test_case_attr"))
+    {
+        if default_test_attr.is_some() {
+            errors.push(syn::Error::new_spanned(
+                attribute,
+                "Test case attr attribute is only allowed once.",
+            ));
+            continue;
+        }
+        match attribute.parse_args::<syn::Meta>() {
+            Ok(meta) => default_test_attr = Some(meta),
+            Err(_) => errors.push(syn::Error::new_spanned(attribute, TEST_ATTR_ERROR)),
+        }
+    }
+    default_test_attr
+}
+
+// Test attribute of an async runtime feature for async test functions without a test attribute
+fn runtime_test_attr() -> Option<syn::Meta> {
+    if cfg!(feature = "tokio") {
+        Some(syn::parse_quote! { ::tokio::test })
+    } else if cfg!(feature = "async-std") {
+        Some(syn::parse_quote! { ::async_std::test })
+    } else {
+        None
+    }
+}
+
+// Timeout of the `#[test_case_timeout]` attribute which applies to all test cases of the function
+fn collect_default_timeout(input: &syn::ItemFn, errors: &mut Vec<syn::Error>) -> Option<syn::Lit> {
+    let mut default_timeout = None;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| path_ends_with(&attribute.path, "test_case_timeout"))
+    {
+        if default_timeout.is_some() {
+            errors.push(syn::Error::new_spanned(
+                attribute,
+                "Test case timeout attribute is only allowed once.",
+            ));
+            continue;
+        }
+        match attribute.parse_args::<syn::Lit>() {
+            Ok(timeout) => match check_timeout_literal(&timeout) {
+                Ok(()) => default_timeout = Some(timeout),
+                Err(e) => errors.push(e),
             },
-            Err(e) => panic!("Could not determine meta data. Error {}.", e),
+            Err(e) => errors.push(e),
         }
     }
-    test_case_descriptions
+    default_timeout
+}
+
+const TIMEOUT_TYPE_ERROR: &str =
+    "Unexpected type for timeout. Expected integer in ms or duration string such as \"2s\".";
+
+fn check_timeout_literal(timeout: &syn::Lit) -> syn::Result<()> {
+    match timeout {
+        syn::Lit::Str(_) | syn::Lit::Int(_) => Ok(()),
+        lit => Err(syn::Error::new_spanned(lit, TIMEOUT_TYPE_ERROR)),
+    }
 }
 
 struct TestCaseAttributes {
     args: Vec<syn::Expr>,
-    custom_name: Option<String>,
+    expected: Option<Expectation>,
+    // Name or name template of the `name` keyword
+    custom_name: Option<syn::LitStr>,
+    description: Option<String>,
+    timeout: Option<syn::Lit>,
+    // Attributes of the `panics` and `ignore` keywords
+    attributes: Vec<syn::Attribute>,
+    case_type: Option<syn::Type>,
+    test_attr: Option<syn::Meta>,
 }
 
-fn parse_test_case_attributes(attr: &syn::AttributeArgs) -> TestCaseAttributes {
+fn parse_test_case_attributes(
+    attr: TestCaseArgs,
+    errors: &mut Vec<syn::Error>,
+) -> TestCaseAttributes {
     let mut args: Vec<syn::Expr> = vec![];
-    let mut custom_name: Option<String> = None;
+    let mut custom_name: Option<syn::LitStr> = None;
+    let mut timeout: Option<syn::Lit> = None;
+    let mut panics: Option<syn::LitStr> = None;
+    let mut ignore: Option<syn::LitStr> = None;
+    let mut test_attr: Option<syn::Meta> = None;
 
-    for a in attr {
-        match a {
-            syn::NestedMeta::Meta(m) => match m {
-                syn::Meta::Path(path) => {
-                    args.push(syn::ExprPath { attrs: vec![], qself: None, path: path.clone() }.into());
+    for expr in attr.args {
+        let assign = match expr {
+            syn::Expr::Assign(assign) => assign,
+            expr => {
+                args.push(expr);
+                continue;
+            }
+        };
+        let key = match &*assign.left {
+            syn::Expr::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+            _ => None,
+        };
+        let value = match &*assign.right {
+            syn::Expr::Lit(lit) => Some(&lit.lit),
+            _ => None,
+        };
+        match key.as_deref() {
+            Some("timeout") => {
+                if timeout.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        assign,
+                        "Timeout can only be defined once.",
+                    ));
+                    continue;
                 }
-                syn::Meta::List(_) => {
-                    panic!("Metalist not expected.");
+                let result = match value {
+                    Some(lit) => check_timeout_literal(lit).map(|()| lit.clone()),
+                    None => Err(syn::Error::new_spanned(&assign.right, TIMEOUT_TYPE_ERROR)),
+                };
+                match result {
+                    Ok(lit) => timeout = Some(lit),
+                    Err(e) => errors.push(e),
                 }
-                syn::Meta::NameValue(nv) => {
-                    let identifier = nv.path.get_ident().expect("Expected identifier!");
-                    if identifier == "test_name" || identifier == "name" {
-                        if custom_name.is_some() {
-                            panic!("Test name can only be defined once.");
-                        }
-                        match &nv.lit {
-                            syn::Lit::Str(_) => {
-                                custom_name = Some(syn_helper::lit_to_str(&nv.lit));
-                            }
-                            _ => unimplemented!("Unexpected type for test name. Expected string."),
-                        }
-                    } else {
-                        panic!("Unexpected identifier '{}'", identifier)
-                    }
+            }
+            Some("test_name") | Some("name") => {
+                if custom_name.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        assign,
+                        "Test name can only be defined once.",
+                    ));
+                    continue;
+                }
+                match value {
+                    Some(syn::Lit::Str(lit)) => custom_name = Some(lit.clone()),
+                    _ => errors.push(syn::Error::new_spanned(
+                        &assign.right,
+                        "Unexpected type for test name. Expected string.",
+                    )),
+                }
+            }
+            Some(key @ "panics") | Some(key @ "ignore") => {
+                let target = if key == "panics" {
+                    &mut panics
+                } else {
+                    &mut ignore
+                };
+                if target.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        &assign,
+                        format!("`{}` can only be defined once.", key),
+                    ));
+                    continue;
+                }
+                match value {
+                    Some(syn::Lit::Str(lit)) => *target = Some(lit.clone()),
+                    _ => errors.push(syn::Error::new_spanned(
+                        &assign.right,
+                        format!("Unexpected type for `{}`. Expected string.", key),
+                    )),
+                }
+            }
+            Some("test_attr") => {
+                if test_attr.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        &assign,
+                        "Test attribute can only be defined once.",
+                    ));
+                    continue;
+                }
+                match parse_test_attr(&assign.right) {
+                    Ok(meta) => test_attr = Some(meta),
+                    Err(e) => errors.push(e),
                 }
-            },
-            syn::NestedMeta::Lit(lit) => {
-                args.push(syn::ExprLit { attrs: vec![], lit: lit.clone() }.into());
             }
+            _ => errors.push(syn::Error::new_spanned(
+                &assign.left,
+                "Unexpected identifier. Expected `name`, `timeout`, `panics`, `ignore`, or `test_attr`.",
+            )),
         }
     }
+    let description = attr.description.and_then(|description| {
+        if custom_name.is_some() {
+            errors.push(syn::Error::new_spanned(
+                &description,
+                "Test name can only be defined once.",
+            ));
+            return None;
+        }
+        syn_helper::lit_to_str(&syn::Lit::Str(description)).ok()
+    });
+    let mut attributes: Vec<syn::Attribute> = vec![];
+    if let Some(panics) = panics {
+        attributes.push(syn::parse_quote! { #[should_panic(expected = #panics)] });
+    }
+    if let Some(ignore) = ignore {
+        attributes.push(syn::parse_quote! { #[ignore = #ignore] });
+    }
     TestCaseAttributes {
         args,
+        expected: attr.expected,
         custom_name,
+        description,
+        timeout,
+        attributes,
+        case_type: attr.case_type,
+        test_attr,
     }
 }
 
-fn calculate_test_name(attr: &TestCaseAttributes, fn_name: &str) -> String {
-    let mut name = "".to_string();
-    match &attr.custom_name {
-        None => {
-            name.push_str(fn_name);
-            for expr in &attr.args {
-                match expr {
-                    syn::Expr::Lit(lit) => name.push_str(&format!("_{}", syn_helper::lit_to_str(&lit.lit))),
-                    syn::Expr::Path(path) => name.push_str(&format!("_{}", path.path.segments.last().expect("Path to contain at least one segment").ident)),
-                    _ => unimplemented!("Unexpected expr type when calculating test name."),
-                }
-            }
+const TEST_ATTR_ERROR: &str =
+    "Expected a test attribute such as `tokio::test` or `tokio::test(flavor = \"multi_thread\")`.";
+
+// Test attribute of the `test_attr` keyword. It is written as a path or as a call such as
+// `tokio::test(flavor = "multi_thread")`.
+fn parse_test_attr(expr: &syn::Expr) -> syn::Result<syn::Meta> {
+    match expr {
+        syn::Expr::Path(syn::ExprPath { qself: None, .. })
+        | syn::Expr::Call(syn::ExprCall { .. }) => syn::parse2(quote! { #expr })
+            .map_err(|_| syn::Error::new_spanned(expr, TEST_ATTR_ERROR)),
+        _ => Err(syn::Error::new_spanned(expr, TEST_ATTR_ERROR)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub fn error_messages(result: syn::Result<proc_macro2::TokenStream>) -> Vec<String> {
+        result
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reports_surplus_arguments() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1, 2, 3 },
+            quote! { fn test(x: u32) {} },
+        ));
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("Expected 1 argument(s), found 3"));
+    }
+
+    #[test]
+    fn reports_errors_of_all_test_cases() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1, name = 5 },
+            quote! {
+                #[test_case(2, label = "two")]
+                #[test_case]
+                fn test(x: u32, y: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Unexpected type for test name. Expected string.",
+                "Unexpected identifier. Expected `name`, `timeout`, `panics`, `ignore`, or `test_attr`.",
+                "Test case attributes need at least one argument such as #[test_case(42)].",
+                "Test case arguments and function input signature mismatch. Missing argument(s) for `y`.",
+                "Test case arguments and function input signature mismatch. Missing argument(s) for `y`.",
+            ]
+        );
+    }
+
+    #[test]
+    fn applies_test_case_keywords() {
+        let result = expand_test_case(
+            quote! { 0, panics = "zero" },
+            quote! {
+                #[test_case(7, ignore = "slow")]
+                #[test_case(3; "three is prime")]
+                #[test_case(5, name = "five"; "prime")]
+                fn test(x: u32) {}
+            },
+        );
+        assert_eq!(
+            error_messages(result),
+            ["Test name can only be defined once."]
+        );
+        let result = expand_test_case(
+            quote! { 0, panics = "zero" },
+            quote! {
+                #[test_case(7, ignore = "slow")]
+                #[test_case(3; "three is prime")]
+                #[inline]
+                fn test(x: u32) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(result.contains("# [should_panic (expected = \"zero\")] fn test_0"));
+        assert!(result.contains("# [ignore = \"slow\"] fn test_7"));
+        assert!(result.contains("# [inline] fn test_three_is_prime"));
+    }
+
+    #[test]
+    fn instantiates_generic_test_cases() {
+        let result = expand_test_case(
+            quote! { 1, type = u32 },
+            quote! {
+                #[test_case(2)]
+                #[test_case_types(u8, Vec<u8>)]
+                fn test<T>(x: u8) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(result.contains("fn test_1_u32 () { type T = u32 ;"));
+        assert!(result.contains("fn test_2_u8 () { type T = u8 ;"));
+        assert!(result.contains("fn test_2_Vec_u8 () { type T = Vec < u8 > ;"));
+    }
+
+    #[test]
+    fn reports_invalid_test_case_types() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1 },
+            quote! {
+                #[test_case(2, type = u8)]
+                fn test<'a, T, U>(x: u8) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Only type parameters are supported for generic test cases.",
+                "Generic test functions need the types of their test cases such as #[test_case_types(u8, u16)] or #[test_case(5, type = u32)].",
+                "Expected a tuple of 2 types for the type parameters such as `(u8, String)`.",
+            ]
+        );
+        let messages = error_messages(expand_test_case_types(
+            quote! { u8 },
+            quote! {
+                fn test() {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            ["Test case types require a generic test function such as `fn test<T>()`."]
+        );
+    }
+
+    #[test]
+    fn adds_timeout_to_test_cases() {
+        let result = expand_test_case(
+            quote! { 1, timeout = "2s" },
+            quote! {
+                #[test_case(2)]
+                #[test_case(3)]
+                #[timeout(50)]
+                #[test_case_timeout(100)]
+                fn test(x: u32) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert_eq!(result.matches("timeout (\"2s\")").count(), 1);
+        assert_eq!(result.matches("timeout (100)").count(), 1);
+        assert_eq!(result.matches("timeout (50)").count(), 1);
+    }
+
+    #[test]
+    fn sets_test_attributes() {
+        let result = expand_test_case(
+            quote! { 1 },
+            quote! {
+                #[test_case(2, test_attr = tokio::test(flavor = "multi_thread"))]
+                fn test(x: u32) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert_eq!(result.matches("# [test]").count(), 1);
+        assert!(result.contains("# [tokio :: test (flavor = \"multi_thread\")]"));
+        let result = expand_test_case(
+            quote! { 1 },
+            quote! {
+                #[test_case(2, test_attr = async_std::test)]
+                #[test_case_attr(tokio::test)]
+                async fn test(x: u32) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(result.contains("# [tokio :: test] # [allow (non_snake_case)] async fn test_1 ()"));
+        assert!(
+            result.contains("# [async_std :: test] # [allow (non_snake_case)] async fn test_2 ()")
+        );
+    }
+
+    #[test]
+    fn reports_invalid_test_attributes() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1, test_attr = tokio::test, test_attr = tokio::test },
+            quote! {
+                #[test_case(2, test_attr = "tokio")]
+                #[test_case_attr(tokio::test)]
+                #[test_case_attr(1)]
+                fn test(x: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Test attribute can only be defined once.",
+                "Expected a test attribute such as `tokio::test` or `tokio::test(flavor = \"multi_thread\")`.",
+                "Test case attr attribute is only allowed once.",
+            ]
+        );
+        let messages = error_messages(expand_test_case_attr(
+            quote! { tokio::test },
+            quote! { fn test() {} },
+        ));
+        assert_eq!(
+            messages,
+            ["The test case attr attribute is only allowed on functions with test cases."]
+        );
+        if runtime_test_attr().is_none() && !cfg!(feature = "smol") {
+            let messages = error_messages(expand_test_case(
+                quote! { 1 },
+                quote! { async fn test(x: u32) {} },
+            ));
+            assert!(messages[0].starts_with("Async test cases need the test attribute"));
         }
-        Some(custom_name) => name = custom_name.to_string(),
     }
-    name
+
+    #[test]
+    fn reports_invalid_timeouts() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1, timeout = 5, timeout = 6 },
+            quote! {
+                #[test_case(2, timeout = true)]
+                #[test_case_timeout(1.5)]
+                fn test(x: u32) {}
+            },
+        ));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0], "Timeout can only be defined once.");
+        assert!(messages[1].starts_with("Unexpected type for timeout"));
+        assert!(messages[2].starts_with("Unexpected type for timeout"));
+    }
+
+    #[test]
+    fn reports_expected_result_without_return_value() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1 => 2 },
+            quote! {
+                #[test_case(2 => matches Some(_))]
+                #[test_case(3)]
+                fn test(x: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "An expected result requires a test function which returns a value.",
+                "An expected result requires a test function which returns a value.",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_expected_result() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1 => 2 => 3 },
+            quote! { fn test(x: u32) -> u32 { x } },
+        ));
+        assert_eq!(messages[0], "expected `,`");
+    }
+
+    #[test]
+    fn reports_unsupported_parameters() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1 },
+            quote! { fn test((a, b): (u32, u32)) {} },
+        ));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("Unexpected function identifier"));
+    }
 }
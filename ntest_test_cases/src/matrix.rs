@@ -0,0 +1,157 @@
+//! Test cases of `#[test_matrix]` and `#[test_pairwise]` which are built from arrays of values.
+
+use crate::{pairwise, syn_helper, CaseKind, TestCaseAttributes};
+use proc_macro2::Span;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+
+// Cross product of the arrays of values for a matrix. The values of the first array change the
+// slowest. Pairwise test cases cover every combination of values of `strength` parameters.
+pub fn parse_test_matrix(
+    kind: CaseKind,
+    args: proc_macro2::TokenStream,
+    span: Span,
+    errors: &mut Vec<syn::Error>,
+) -> Vec<TestCaseAttributes> {
+    let arrays = match Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated.parse2(args) {
+        Ok(arrays) => arrays,
+        Err(e) => {
+            errors.push(e);
+            return vec![];
+        }
+    };
+    let mut values: Vec<Vec<syn::Expr>> = vec![];
+    let mut strength: Option<usize> = None;
+    let mut valid = true;
+    for array in arrays {
+        match array {
+            syn::Expr::Array(array) if !array.elems.is_empty() => {
+                values.push(array.elems.into_iter().collect())
+            }
+            syn::Expr::Assign(assign)
+                if kind == CaseKind::Pairwise && syn_helper::is_ident(&assign.left, "strength") =>
+            {
+                if strength.is_some() {
+                    valid = false;
+                    errors.push(syn::Error::new_spanned(
+                        assign,
+                        "Strength can only be defined once.",
+                    ));
+                    continue;
+                }
+                match &*assign.right {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(lit),
+                        ..
+                    }) if lit
+                        .base10_parse::<usize>()
+                        .is_ok_and(|strength| strength > 0) =>
+                    {
+                        strength = lit.base10_parse().ok()
+                    }
+                    right => {
+                        valid = false;
+                        errors.push(syn::Error::new_spanned(
+                            right,
+                            "Expected a positive integer as strength such as `strength = 3`.",
+                        ))
+                    }
+                }
+            }
+            array => {
+                valid = false;
+                errors.push(syn::Error::new_spanned(
+                    array,
+                    "Expected a non empty array of values such as [1, 2, 3].",
+                ))
+            }
+        }
+    }
+    if values.is_empty() && valid {
+        errors.push(syn::Error::new(span, kind.missing_arguments_message()));
+    }
+    if !valid || values.is_empty() {
+        return vec![];
+    }
+    let combinations = match kind {
+        CaseKind::Pairwise => pairwise::combinations(&values, strength.unwrap_or(2)),
+        _ => cross_product(&values),
+    };
+    combinations
+        .into_iter()
+        .map(|args| TestCaseAttributes {
+            args,
+            expected: None,
+            custom_name: None,
+            description: None,
+            timeout: None,
+            attributes: vec![],
+            case_type: None,
+            test_attr: None,
+        })
+        .collect()
+}
+
+fn cross_product(values: &[Vec<syn::Expr>]) -> Vec<Vec<syn::Expr>> {
+    let mut combinations: Vec<Vec<syn::Expr>> = vec![vec![]];
+    for parameter_values in values {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                parameter_values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push(value.clone());
+                    combination
+                })
+            })
+            .collect();
+    }
+    combinations
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::error_messages;
+    use crate::{expand_test_case, expand_test_matrix};
+    use quote::quote;
+
+    #[test]
+    fn expands_test_matrix() {
+        let result = expand_test_matrix(
+            quote! { [1, 2], ["a", "b"] },
+            quote! {
+                #[should_panic]
+                #[test_case(3, "c")]
+                fn test(x: u32, y: &str) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        for name in ["test_1_a", "test_1_b", "test_2_a", "test_2_b", "test_3_c"] {
+            assert!(result.contains(&format!("fn {} ()", name)), "{}", name);
+        }
+        assert_eq!(result.matches("should_panic").count(), 4);
+    }
+
+    #[test]
+    fn reports_invalid_test_matrices() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1, "a" },
+            quote! {
+                #[test_matrix([1, 2], ["a"])]
+                #[test_matrix([1, 2], [], 3)]
+                #[test_matrix([1], ["a"], [true])]
+                fn test(x: u32, y: &str) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Expected a non empty array of values such as [1, 2, 3].",
+                "Expected a non empty array of values such as [1, 2, 3].",
+                "Test case arguments and function input signature mismatch. Expected 2 argument(s), found 3.",
+                "Duplicate test name `test_1_a`. Use `name` to give the test case a unique name.",
+            ]
+        );
+    }
+}
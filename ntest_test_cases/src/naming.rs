@@ -0,0 +1,709 @@
+//! Names of the generated test functions.
+//!
+//! Names are derived from the arguments of a test case, from a description, or from a template such
+//! as `name = "parse_{input}_as_{kind}"`. Characters of other scripts are transliterated to ASCII
+//! and long names are shortened to a prefix and a hash of the full name. Derived names which collide
+//! are first retried with the full paths of their arguments and then numbered in the order of the
+//! test cases. Only explicit names and test cases with the same arguments remain as clashes.
+
+use crate::{syn_helper, TestCaseAttributes, TestDescription};
+use quote::quote;
+use std::collections::{HashMap, HashSet};
+
+/// Names which are longer are cut and end with a hash of the full name.
+pub const MAX_LENGTH: usize = 64;
+
+/// Name of a test case before collisions are resolved.
+pub struct TestName {
+    pub name: String,
+    /// Name with the full paths of the arguments such as `A_X` for `A::X`. Names given with `name`
+    /// or a description are kept as they are and have none.
+    pub qualified: Option<String>,
+    /// Source of the arguments. Test cases with the same source are duplicates.
+    pub source: String,
+}
+
+/// Unique names of the test cases in the order of the given names. Names which can not be made
+/// unique are returned as error together with the index of their first clash.
+pub fn unique_names(names: &[TestName]) -> Result<Vec<String>, Vec<(String, usize)>> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in names {
+        *counts.entry(&name.name).or_default() += 1;
+    }
+    let explicit: HashSet<&str> = names
+        .iter()
+        .filter(|name| name.qualified.is_none())
+        .map(|name| name.name.as_str())
+        .collect();
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut sources: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut clashes: Vec<(String, usize)> = vec![];
+    let mut result = vec![];
+    for (index, name) in names.iter().enumerate() {
+        let base = match &name.qualified {
+            Some(qualified) if counts[name.name.as_str()] > 1 => qualified.clone(),
+            _ => name.name.clone(),
+        };
+        let sources = sources.entry(base.clone()).or_default();
+        let duplicate = sources.contains(&name.source.as_str());
+        sources.push(&name.source);
+        if name.qualified.is_none() || duplicate {
+            if !taken.insert(base.clone()) && clashes.iter().all(|(clash, _)| *clash != base) {
+                clashes.push((base.clone(), index));
+            }
+            result.push(base);
+            continue;
+        }
+        let mut unique = base.clone();
+        let mut counter = 1;
+        while taken.contains(&unique) || explicit.contains(unique.as_str()) {
+            counter += 1;
+            unique = format!("{}_{}", base, counter);
+        }
+        taken.insert(unique.clone());
+        result.push(unique);
+    }
+    if clashes.is_empty() {
+        Ok(result)
+    } else {
+        Err(clashes)
+    }
+}
+
+/// Unique names of distinct tests such as the rows of a data file. Collisions are numbered.
+pub fn number_collisions(names: Vec<String>) -> Vec<String> {
+    let names: Vec<TestName> = names
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| TestName {
+            qualified: Some(name.clone()),
+            name,
+            source: index.to_string(),
+        })
+        .collect();
+    // Distinct sources never clash
+    unique_names(&names).unwrap_or_default()
+}
+
+/// Cuts names longer than [MAX_LENGTH] and appends a hash of the full name.
+pub fn cap_length(name: String) -> String {
+    if name.len() <= MAX_LENGTH {
+        return name;
+    }
+    let hash = format!("_{:08x}", fnv1a(&name));
+    let mut end = MAX_LENGTH - hash.len();
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &name[..end], hash)
+}
+
+// The hash must not change between builds and rust versions
+fn fnv1a(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Part of a name template such as `parse_{input}_as_{kind}`.
+#[derive(Debug, PartialEq)]
+pub enum TemplatePart {
+    Text(String),
+    Parameter(String),
+}
+
+/// Splits a template into text and parameters. `{{` and `}}` are literal braces.
+pub fn parse_template(template: &str) -> Result<Vec<TemplatePart>, String> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut parameter = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => parameter.push(c),
+                        None => return Err("Unclosed `{` in test name template.".to_string()),
+                    }
+                }
+                let parameter = parameter.trim().to_string();
+                if parameter.is_empty() {
+                    return Err(
+                        "Expected a parameter name between the braces of the test name template such as `{input}`."
+                            .to_string(),
+                    );
+                }
+                if !text.is_empty() {
+                    parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                }
+                parts.push(TemplatePart::Parameter(parameter));
+            }
+            '}' => {
+                return Err(
+                    "Unexpected `}` in test name template. Use `}}` for a brace.".to_string(),
+                )
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(TemplatePart::Text(text));
+    }
+    Ok(parts)
+}
+
+/// ASCII spelling of letters of other scripts such as `ae` for `ä` or `zh` for `ж`.
+pub fn transliterate(c: char) -> Option<&'static str> {
+    let ascii = match c {
+        'à' | 'á' | 'â' | 'ã' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'ä' | 'æ' => "ae",
+        'Ä' | 'Æ' => "Ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ď' | 'đ' | 'ð' => "d",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĥ' | 'ħ' => "h",
+        'Ĥ' | 'Ħ' => "H",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ĵ' => "j",
+        'Ĵ' => "J",
+        'ķ' => "k",
+        'Ķ' => "K",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ö' | 'œ' => "oe",
+        'Ö' | 'Œ' => "Oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'þ' => "th",
+        'Þ' => "Th",
+        'ù' | 'ú' | 'û' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ù' | 'Ú' | 'Û' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ü' => "ue",
+        'Ü' => "Ue",
+        'ŵ' => "w",
+        'Ŵ' => "W",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ý' | 'Ÿ' | 'Ŷ' => "Y",
+        'ź' | 'ż' | 'ž' => "z",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        // Greek
+        'α' | 'ά' => "a",
+        'β' => "b",
+        'γ' => "g",
+        'δ' => "d",
+        'ε' | 'έ' => "e",
+        'ζ' => "z",
+        'η' | 'ή' => "i",
+        'θ' => "th",
+        'ι' | 'ί' | 'ϊ' => "i",
+        'κ' => "k",
+        'λ' => "l",
+        'μ' => "m",
+        'ν' => "n",
+        'ξ' => "x",
+        'ο' | 'ό' => "o",
+        'π' => "p",
+        'ρ' => "r",
+        'σ' | 'ς' => "s",
+        'τ' => "t",
+        'υ' | 'ύ' | 'ϋ' => "y",
+        'φ' => "f",
+        'χ' => "ch",
+        'ψ' => "ps",
+        'ω' | 'ώ' => "o",
+        'Α' | 'Ά' => "A",
+        'Β' => "B",
+        'Γ' => "G",
+        'Δ' => "D",
+        'Ε' | 'Έ' => "E",
+        'Ζ' => "Z",
+        'Η' | 'Ή' => "I",
+        'Θ' => "Th",
+        'Ι' | 'Ί' => "I",
+        'Κ' => "K",
+        'Λ' => "L",
+        'Μ' => "M",
+        'Ν' => "N",
+        'Ξ' => "X",
+        'Ο' | 'Ό' => "O",
+        'Π' => "P",
+        'Ρ' => "R",
+        'Σ' => "S",
+        'Τ' => "T",
+        'Υ' | 'Ύ' => "Y",
+        'Φ' => "F",
+        'Χ' => "Ch",
+        'Ψ' => "Ps",
+        'Ω' | 'Ώ' => "O",
+        // Cyrillic
+        'а' => "a",
+        'б' => "b",
+        'в' => "v",
+        'г' => "g",
+        'д' => "d",
+        'е' | 'ё' | 'э' => "e",
+        'ж' => "zh",
+        'з' => "z",
+        'и' | 'й' | 'ы' | 'і' | 'ї' => "i",
+        'к' => "k",
+        'л' => "l",
+        'м' => "m",
+        'н' => "n",
+        'о' => "o",
+        'п' => "p",
+        'р' => "r",
+        'с' => "s",
+        'т' => "t",
+        'у' => "u",
+        'ф' => "f",
+        'х' => "kh",
+        'ц' => "ts",
+        'ч' => "ch",
+        'ш' => "sh",
+        'щ' => "shch",
+        'ъ' | 'ь' => "",
+        'ю' => "yu",
+        'я' => "ya",
+        'А' => "A",
+        'Б' => "B",
+        'В' => "V",
+        'Г' => "G",
+        'Д' => "D",
+        'Е' | 'Ё' | 'Э' => "E",
+        'Ж' => "Zh",
+        'З' => "Z",
+        'И' | 'Й' | 'Ы' | 'І' | 'Ї' => "I",
+        'К' => "K",
+        'Л' => "L",
+        'М' => "M",
+        'Н' => "N",
+        'О' => "O",
+        'П' => "P",
+        'Р' => "R",
+        'С' => "S",
+        'Т' => "T",
+        'У' => "U",
+        'Ф' => "F",
+        'Х' => "Kh",
+        'Ц' => "Ts",
+        'Ч' => "Ch",
+        'Ш' => "Sh",
+        'Щ' => "Shch",
+        'Ъ' | 'Ь' => "",
+        'Ю' => "Yu",
+        'Я' => "Ya",
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+// Function name and parameter names which test names are derived from
+pub struct TestNaming<'a> {
+    pub fn_name: &'a str,
+    pub params: &'a [String],
+}
+
+// Name of the test case and the name with the full paths of the arguments. Names of the `name`
+// keyword without placeholders and descriptions are explicit and have no qualified name.
+pub fn calculate_test_name(
+    attr: &TestCaseAttributes,
+    names: &TestNaming,
+    errors: &mut Vec<syn::Error>,
+) -> (String, Option<String>) {
+    let fn_name = names.fn_name;
+    if let Some(custom_name) = &attr.custom_name {
+        let value = custom_name.value();
+        if !value.contains(['{', '}']) {
+            let name = syn_helper::string_to_identifier(&value);
+            if !check_identifier(&name, custom_name, errors) {
+                // The name of the function is numbered and does not add a clash to the error
+                return (fn_name.to_string(), Some(fn_name.to_string()));
+            }
+            return (name, None);
+        }
+        let parts = match parse_template(&value) {
+            Ok(parts) => parts,
+            Err(message) => {
+                errors.push(syn::Error::new_spanned(custom_name, message));
+                return (fn_name.to_string(), None);
+            }
+        };
+        let mut name = String::new();
+        let mut qualified = String::new();
+        for part in parts {
+            match part {
+                TemplatePart::Text(text) => {
+                    let text = syn_helper::string_to_identifier(&text);
+                    name.push_str(&text);
+                    qualified.push_str(&text);
+                }
+                TemplatePart::Parameter(param) => {
+                    match names.params.iter().position(|name| *name == param) {
+                        Some(index) => {
+                            if let Some(arg) = attr.args.get(index) {
+                                name.push_str(&syn_helper::expr_to_identifier(arg));
+                                qualified.push_str(&syn_helper::expr_to_qualified_identifier(arg));
+                            }
+                        }
+                        None => errors.push(syn::Error::new_spanned(
+                            custom_name,
+                            format!(
+                                "Unknown parameter `{}` in test name template. Expected one of {}.",
+                                param,
+                                names
+                                    .params
+                                    .iter()
+                                    .map(|name| format!("`{}`", name))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            ),
+                        )),
+                    }
+                }
+            }
+        }
+        if !check_identifier(&name, custom_name, errors) {
+            return (fn_name.to_string(), Some(fn_name.to_string()));
+        }
+        return (cap_length(name), Some(cap_length(qualified)));
+    }
+    if let Some(description) = &attr.description {
+        return (cap_length(format!("{}_{}", fn_name, description)), None);
+    }
+    let mut name = fn_name.to_string();
+    let mut qualified = fn_name.to_string();
+    for expr in &attr.args {
+        let identifier = syn_helper::expr_to_identifier(expr);
+        if !identifier.is_empty() {
+            name.push_str(&format!("_{}", identifier));
+        }
+        let identifier = syn_helper::expr_to_qualified_identifier(expr);
+        if !identifier.is_empty() {
+            qualified.push_str(&format!("_{}", identifier));
+        }
+    }
+    if let Some(case_type) = &attr.case_type {
+        let type_name = syn_helper::type_to_identifier(case_type);
+        name.push_str(&format!("_{}", type_name));
+        qualified.push_str(&format!("_{}", type_name));
+    }
+    (cap_length(name), Some(cap_length(qualified)))
+}
+
+// Names given with `name` may start with a digit, be empty, or be a keyword
+fn check_identifier(name: &str, custom_name: &syn::LitStr, errors: &mut Vec<syn::Error>) -> bool {
+    if syn::parse_str::<syn::Ident>(name).is_ok() {
+        return true;
+    }
+    let message = if name.is_empty() {
+        "The test name is empty. Add text to the name such as `case_{x}`.".to_string()
+    } else {
+        format!(
+            "The test name `{}` is not a valid identifier. Start the name with a letter such as `case_{{x}}`.",
+            name
+        )
+    };
+    errors.push(syn::Error::new_spanned(custom_name, message));
+    false
+}
+
+// Resolves collisions of derived names. Remaining clashes are reported together.
+pub fn assign_unique_names(
+    mut test_descriptions: Vec<TestDescription>,
+    errors: &mut Vec<syn::Error>,
+) -> Vec<TestDescription> {
+    let names: Vec<TestName> = test_descriptions
+        .iter()
+        .map(|test_description| {
+            let args = &test_description.args;
+            let case_type = &test_description.case_type;
+            TestName {
+                name: test_description.name.clone(),
+                qualified: test_description.qualified_name.clone(),
+                source: quote! { #(#args),* ; #case_type }.to_string(),
+            }
+        })
+        .collect();
+    match unique_names(&names) {
+        Ok(names) => {
+            for (test_description, name) in test_descriptions.iter_mut().zip(names) {
+                test_description.name = name;
+            }
+        }
+        Err(clashes) => {
+            let span = test_descriptions[clashes[0].1].span;
+            let names: Vec<String> = clashes
+                .iter()
+                .map(|(name, _)| format!("`{}`", name))
+                .collect();
+            errors.push(syn::Error::new(
+                span,
+                if names.len() == 1 {
+                    format!(
+                        "Duplicate test name {}. Use `name` to give the test case a unique name.",
+                        names[0]
+                    )
+                } else {
+                    format!(
+                        "Duplicate test names {}. Use `name` to give the test cases unique names.",
+                        names.join(", ")
+                    )
+                },
+            ));
+        }
+    }
+    test_descriptions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expand_test_case;
+    use crate::tests::error_messages;
+
+    fn derived(name: &str, qualified: &str, source: &str) -> TestName {
+        TestName {
+            name: name.to_string(),
+            qualified: Some(qualified.to_string()),
+            source: source.to_string(),
+        }
+    }
+
+    fn explicit(name: &str, source: &str) -> TestName {
+        TestName {
+            name: name.to_string(),
+            qualified: None,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn disambiguates_derived_names() {
+        let names = [
+            derived("test_X", "test_A_X", "A :: X"),
+            derived("test_X", "test_B_X", "B :: X"),
+            derived("test_a_b", "test_a_b", "\"a b\""),
+            derived("test_a_b", "test_a_b", "\"a_b\""),
+            explicit("test_1", "2"),
+            derived("test_1", "test_1", "1"),
+            derived("test_2", "test_2", "2"),
+        ];
+        assert_eq!(
+            unique_names(&names).unwrap(),
+            [
+                "test_A_X",
+                "test_B_X",
+                "test_a_b",
+                "test_a_b_2",
+                "test_1",
+                "test_1_2",
+                "test_2"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_remaining_clashes() {
+        let names = [
+            derived("test_1", "test_1", "1"),
+            explicit("b", "2"),
+            derived("test_1", "test_1", "1"),
+            explicit("b", "3"),
+            derived("test_1", "test_1", "1"),
+        ];
+        assert_eq!(
+            unique_names(&names).unwrap_err(),
+            [("test_1".to_string(), 2), ("b".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn caps_long_names_with_hash() {
+        assert_eq!(cap_length("short".to_string()), "short");
+        let long = "a".repeat(100);
+        let capped = cap_length(long.clone());
+        assert_eq!(capped.len(), MAX_LENGTH);
+        assert!(capped.starts_with(&"a".repeat(MAX_LENGTH - 9)));
+        assert_eq!(capped, cap_length(long));
+        assert_ne!(capped, cap_length(format!("{}b", "a".repeat(99))));
+        assert_eq!(cap_length("ä".repeat(40)).len(), MAX_LENGTH - 1);
+    }
+
+    #[test]
+    fn parses_templates() {
+        assert_eq!(
+            parse_template("parse_{input}_as_{ kind }{{x}}").unwrap(),
+            [
+                TemplatePart::Text("parse_".to_string()),
+                TemplatePart::Parameter("input".to_string()),
+                TemplatePart::Text("_as_".to_string()),
+                TemplatePart::Parameter("kind".to_string()),
+                TemplatePart::Text("{x}".to_string()),
+            ]
+        );
+        assert!(parse_template("a_{input").is_err());
+        assert!(parse_template("a_{}").is_err());
+        assert!(parse_template("a}").is_err());
+    }
+
+    #[test]
+    fn names_test_cases_after_expressions() {
+        let result = expand_test_case(
+            quote! { -1, Test::A },
+            quote! {
+                #[test_case(Some(3), (1, "a b"))]
+                #[test_case(vec![1, 2], Duration::from_millis(5))]
+                #[test_case(|x| x + 1, [a - b, a * b])]
+                #[test_case(Point { x: 1, y: 2 }, r#type)]
+                fn test(x: u32, y: u32) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        for name in [
+            "test_n1_A",
+            "test_Some_3_1_a_b",
+            "test_vec_1_2_Duration_from_millis_5",
+            "test_x_x_plus_1_a_minus_b_a_times_b",
+            "test_Point_x_1_y_2_type",
+        ] {
+            assert!(result.contains(&format!("fn {} ()", name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn disambiguates_test_names() {
+        let result = expand_test_case(
+            quote! { 'a', A::X },
+            quote! {
+                #[test_case(b'a', B::X)]
+                #[test_case("ä ß", b"\x01z")]
+                #[test_case("日", Y)]
+                #[test_case("a", X; "a x")]
+                fn test(x: u32, y: u32) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        for name in [
+            "test_a_A_X",
+            "test_a_B_X",
+            "test_ae_ss__x01z",
+            "test__u65e5_Y",
+            "test_a_x",
+        ] {
+            assert!(result.contains(&format!("fn {} ()", name)), "{}", name);
+        }
+        let result = expand_test_case(
+            quote! { "a b" },
+            quote! {
+                #[test_case("a_b")]
+                fn test(x: &str) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        assert!(result.contains("fn test_a_b ()"));
+        assert!(result.contains("fn test_a_b_2 ()"));
+    }
+
+    #[test]
+    fn names_test_cases_with_templates() {
+        let result = expand_test_case(
+            quote! { "42", Kind::Int, name = "parse_{input}_as_{kind}" },
+            quote! {
+                #[test_case("4.2", Kind::Float, name = "parse_{input}_as_{kind}")]
+                #[test_case("4", Other::Float, name = "parse_{input}_as_{kind}")]
+                #[test_case("x", Kind::Int, name = "{{invalid}}_{input}")]
+                fn test(input: &str, kind: Kind) {}
+            },
+        )
+        .unwrap()
+        .to_string();
+        for name in [
+            "parse_42_as_Int",
+            "parse_4_full_stop2_as_Float",
+            "parse_4_as_Float",
+            "_left_braceinvalid_right_brace_x",
+        ] {
+            assert!(result.contains(&format!("fn {} ()", name)), "{}", name);
+        }
+        let messages = error_messages(expand_test_case(
+            quote! { 1, name = "test_{y}" },
+            quote! {
+                #[test_case(2, name = "test_{x")]
+                fn test(x: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Unknown parameter `y` in test name template. Expected one of `x`.",
+                "Unclosed `{` in test name template.",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_test_names_of_templates() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1, name = "{x}" },
+            quote! {
+                #[test_case((), name = "{x}")]
+                #[test_case(2, name = "2")]
+                fn test(x: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "The test name `1` is not a valid identifier. Start the name with a letter such as `case_{x}`.",
+                "The test name is empty. Add text to the name such as `case_{x}`.",
+                "The test name `2` is not a valid identifier. Start the name with a letter such as `case_{x}`.",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_test_names() {
+        let messages = error_messages(expand_test_case(
+            quote! { 1 },
+            quote! {
+                #[test_case(2, name = "two")]
+                #[test_case(1)]
+                #[test_case(3, name = "two")]
+                fn test(x: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            ["Duplicate test names `test_1`, `two`. Use `name` to give the test cases unique names."]
+        );
+    }
+}
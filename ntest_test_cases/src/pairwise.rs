@@ -0,0 +1,217 @@
+//! Test cases of `#[test_pairwise]` which are built from covering arrays.
+//!
+//! A covering array of strength `t` is a set of rows in which every combination of values of any
+//! `t` parameters appears at least once. The rows are built greedily: every row starts with the
+//! first combination which is not covered yet. The remaining parameters get the value which covers
+//! the most new combinations. The construction only depends on the number of values of each
+//! parameter, so the rows are stable between builds.
+
+use std::collections::BTreeSet;
+
+// Values of `t` parameters as pairs of parameter index and value index, sorted by parameter
+type Combination = Vec<(usize, usize)>;
+
+/// Rows of value indices which cover all combinations of `strength` parameters. `sizes` holds the
+/// number of values of every parameter. A strength above the number of parameters yields the full
+/// cross product.
+pub fn covering_array(sizes: &[usize], strength: usize) -> Vec<Vec<usize>> {
+    let strength = strength.min(sizes.len());
+    let mut uncovered: BTreeSet<Combination> = BTreeSet::new();
+    for parameters in subsets(sizes.len(), strength) {
+        let mut combinations: Vec<Combination> = vec![vec![]];
+        for &parameter in &parameters {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    (0..sizes[parameter]).map(move |value| {
+                        let mut combination = combination.clone();
+                        combination.push((parameter, value));
+                        combination
+                    })
+                })
+                .collect();
+        }
+        uncovered.extend(combinations);
+    }
+
+    let mut rows = vec![];
+    while let Some(first) = uncovered.iter().next().cloned() {
+        let mut row: Vec<Option<usize>> = vec![None; sizes.len()];
+        for (parameter, value) in first {
+            row[parameter] = Some(value);
+        }
+        for parameter in 0..sizes.len() {
+            if row[parameter].is_some() {
+                continue;
+            }
+            // Prefers the value which covers the most combinations now and later. The lowest value
+            // wins a tie.
+            let best = (0..sizes[parameter])
+                .max_by_key(|&value| {
+                    let mut covered = 0;
+                    let mut open = 0;
+                    for combination in uncovered
+                        .iter()
+                        .filter(|combination| combination.contains(&(parameter, value)))
+                    {
+                        let compatible = combination.iter().all(|&(other, other_value)| {
+                            other == parameter || row[other].is_none_or(|v| v == other_value)
+                        });
+                        if !compatible {
+                            continue;
+                        }
+                        if combination
+                            .iter()
+                            .all(|&(other, _)| other == parameter || row[other].is_some())
+                        {
+                            covered += 1;
+                        } else {
+                            open += 1;
+                        }
+                    }
+                    (covered, open, std::cmp::Reverse(value))
+                })
+                .unwrap_or(0);
+            row[parameter] = Some(best);
+        }
+        let row: Vec<usize> = row.into_iter().map(|value| value.unwrap_or(0)).collect();
+        for parameters in subsets(sizes.len(), strength) {
+            let combination: Combination = parameters
+                .into_iter()
+                .map(|parameter| (parameter, row[parameter]))
+                .collect();
+            uncovered.remove(&combination);
+        }
+        rows.push(row);
+    }
+    rows
+}
+
+// All sets of `k` indices below `n` in lexicographic order
+fn subsets(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut subsets = vec![];
+    if k > n {
+        return subsets;
+    }
+    let mut current: Vec<usize> = (0..k).collect();
+    loop {
+        subsets.push(current.clone());
+        // Increments the last index which has not reached its maximum
+        let position = match (0..k).rev().find(|&i| current[i] < n - k + i) {
+            Some(position) => position,
+            None => return subsets,
+        };
+        current[position] += 1;
+        for i in position + 1..k {
+            current[i] = current[i - 1] + 1;
+        }
+    }
+}
+
+/// Values of the test cases which cover every combination of values of `strength` parameters.
+pub fn combinations(values: &[Vec<syn::Expr>], strength: usize) -> Vec<Vec<syn::Expr>> {
+    let sizes: Vec<usize> = values.iter().map(Vec::len).collect();
+    covering_array(&sizes, strength)
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .enumerate()
+                .map(|(parameter, value)| values[parameter][value].clone())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expand_test_pairwise;
+    use crate::tests::error_messages;
+    use quote::quote;
+
+    fn assert_covers(sizes: &[usize], strength: usize, rows: &[Vec<usize>]) {
+        for parameters in subsets(sizes.len(), strength) {
+            let mut missing: BTreeSet<Vec<usize>> = BTreeSet::new();
+            let mut values: Vec<Vec<usize>> = vec![vec![]];
+            for &parameter in &parameters {
+                values = values
+                    .into_iter()
+                    .flat_map(|value| {
+                        (0..sizes[parameter]).map(move |v| {
+                            let mut value = value.clone();
+                            value.push(v);
+                            value
+                        })
+                    })
+                    .collect();
+            }
+            missing.extend(values);
+            for row in rows {
+                missing.remove(&parameters.iter().map(|&p| row[p]).collect::<Vec<_>>());
+            }
+            assert!(missing.is_empty(), "{:?} misses {:?}", parameters, missing);
+        }
+    }
+
+    #[test]
+    fn covers_all_pairs() {
+        let sizes = [3, 4, 5, 3, 4, 5, 3, 4];
+        let rows = covering_array(&sizes, 2);
+        assert_covers(&sizes, 2, &rows);
+        // The full cross product has 43200 rows
+        assert!(rows.len() <= 30, "{} rows", rows.len());
+        assert_eq!(rows, covering_array(&sizes, 2));
+    }
+
+    #[test]
+    fn covers_all_triples() {
+        let sizes = [2, 3, 2, 3, 2];
+        let rows = covering_array(&sizes, 3);
+        assert_covers(&sizes, 3, &rows);
+        assert!(rows.len() <= 25, "{} rows", rows.len());
+    }
+
+    #[test]
+    fn strength_above_parameters_is_cross_product() {
+        assert_eq!(covering_array(&[2, 2], 3), [[0, 0], [0, 1], [1, 0], [1, 1]]);
+    }
+
+    #[test]
+    fn expands_pairwise_test_cases() {
+        let expand = |attr| {
+            expand_test_pairwise(
+                attr,
+                quote! {
+                    fn test(a: u32, b: u32, c: u32) {}
+                },
+            )
+            .unwrap()
+            .to_string()
+        };
+        let pairs = expand(quote! { [1, 2], [3, 4], [5, 6] });
+        assert_eq!(pairs.matches("# [test]").count(), 4);
+        assert_eq!(pairs, expand(quote! { [1, 2], [3, 4], [5, 6] }));
+        let triples = expand(quote! { [1, 2], [3, 4], [5, 6], strength = 3 });
+        assert_eq!(triples.matches("# [test]").count(), 8);
+    }
+
+    #[test]
+    fn reports_invalid_pairwise_strength() {
+        let messages = error_messages(expand_test_pairwise(
+            quote! { [1, 2], [3, 4], strength = 0 },
+            quote! {
+                #[test_pairwise([1, 2], [3, 4], strength = 2, strength = 3)]
+                #[test_matrix([1, 2], [3, 4], strength = 2)]
+                fn test(a: u32, b: u32) {}
+            },
+        ));
+        assert_eq!(
+            messages,
+            [
+                "Expected a positive integer as strength such as `strength = 3`.",
+                "Strength can only be defined once.",
+                "Expected a non empty array of values such as [1, 2, 3].",
+            ]
+        );
+    }
+}
@@ -1,25 +1,153 @@
-pub fn lit_to_str(lit: &syn::Lit) -> String {
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::ext::IdentExt;
+
+use crate::naming;
+
+pub fn lit_to_str(lit: &syn::Lit) -> syn::Result<String> {
     match lit {
-        syn::Lit::Bool(s) => s.value.to_string(),
-        syn::Lit::Str(s) => string_to_identifier(&s.value()),
-        syn::Lit::Int(s) => number_to_identifier(s.base10_digits()),
-        syn::Lit::Float(s) => number_to_identifier(s.base10_digits()),
-        _ => unimplemented!("String conversion for literal. Only bool, str, positive int, and float values are supported."),
+        syn::Lit::Bool(s) => Ok(s.value.to_string()),
+        syn::Lit::Str(s) => Ok(string_to_identifier(&s.value())),
+        syn::Lit::Int(s) => Ok(number_to_identifier(s.base10_digits())),
+        syn::Lit::Float(s) => Ok(number_to_identifier(s.base10_digits())),
+        syn::Lit::Char(s) => Ok(string_to_identifier(&s.value().to_string())),
+        syn::Lit::Byte(s) => Ok(bytes_to_identifier(&[s.value()])),
+        syn::Lit::ByteStr(s) => Ok(bytes_to_identifier(&s.value())),
+        _ => Err(syn::Error::new_spanned(
+            lit,
+            "String conversion for literal. Only bool, str, char, byte, byte string, int, and float values are supported.",
+        )),
+    }
+}
+
+/// Derives a readable identifier from a test case argument. Literals and paths keep their established
+/// names. Other expressions such as `Some(3)` or `Duration::from_millis(5)` are named after the
+/// identifiers, literals, and arithmetic or comparison operators they contain.
+pub fn expr_to_identifier(expr: &syn::Expr) -> String {
+    match expr {
+        syn::Expr::Lit(lit) => {
+            lit_to_str(&lit.lit).unwrap_or_else(|_| tokens_to_identifier(lit.to_token_stream()))
+        }
+        syn::Expr::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map_or(String::new(), |segment| segment.ident.unraw().to_string()),
+        // Negative numbers such as `-1` are named `n1`
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) if matches!(**expr, syn::Expr::Lit(_)) => format!("n{}", expr_to_identifier(expr)),
+        // Invisible groups are created when a `macro_rules!` macro passes an expression
+        syn::Expr::Group(group) => expr_to_identifier(&group.expr),
+        expr => tokens_to_identifier(expr.to_token_stream()),
+    }
+}
+
+/// Like [expr_to_identifier] but paths keep all of their segments such as `A_X` for `A::X`.
+pub fn expr_to_qualified_identifier(expr: &syn::Expr) -> String {
+    match expr {
+        syn::Expr::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.unraw().to_string())
+            .collect::<Vec<_>>()
+            .join("_"),
+        syn::Expr::Group(group) => expr_to_qualified_identifier(&group.expr),
+        expr => expr_to_identifier(expr),
+    }
+}
+
+/// Derives an identifier from a type such as `Vec_u8` for `Vec<u8>`.
+pub fn type_to_identifier(ty: &syn::Type) -> String {
+    tokens_to_identifier(ty.to_token_stream())
+}
+
+pub fn is_ident(expr: &syn::Expr, ident: &str) -> bool {
+    matches!(expr, syn::Expr::Path(path) if path.path.is_ident(ident))
+}
+
+fn tokens_to_identifier(tokens: TokenStream) -> String {
+    let mut parts = vec![];
+    collect_identifier_parts(tokens, &mut parts);
+    parts.join("_")
+}
+
+fn collect_identifier_parts(tokens: TokenStream, parts: &mut Vec<String>) {
+    let mut operator = String::new();
+    for token in tokens {
+        match token {
+            TokenTree::Punct(punct) => {
+                operator.push(punct.as_char());
+                if punct.spacing() == Spacing::Joint {
+                    continue;
+                }
+                if let Some(name) = operator_name(&operator) {
+                    parts.push(name.to_string());
+                }
+                operator.clear();
+                continue;
+            }
+            TokenTree::Ident(ident) => parts.push(ident.unraw().to_string()),
+            TokenTree::Literal(literal) => {
+                let lit = syn::Lit::new(literal);
+                parts.push(
+                    lit_to_str(&lit).unwrap_or_else(|_| {
+                        string_to_identifier(&lit.to_token_stream().to_string())
+                    }),
+                );
+            }
+            TokenTree::Group(group) => collect_identifier_parts(group.stream(), parts),
+        }
+        operator.clear();
+    }
+}
+
+// Operators which distinguish otherwise equal expressions such as `a + b` and `a - b`
+fn operator_name(operator: &str) -> Option<&'static str> {
+    match operator {
+        "+" => Some("plus"),
+        "-" => Some("minus"),
+        "*" => Some("times"),
+        "/" => Some("div"),
+        "%" => Some("rem"),
+        "==" => Some("eq"),
+        "!=" => Some("ne"),
+        "<=" => Some("le"),
+        ">=" => Some("ge"),
+        "&&" => Some("and"),
+        "||" => Some("or"),
+        _ => None,
     }
 }
 
+// Printable ASCII bytes are named like characters of a string, other bytes by their hex value
+fn bytes_to_identifier(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            b' '..=b'~' => string_to_identifier(&char::from(byte).to_string()),
+            _ => format!("_x{:02x}", byte),
+        })
+        .collect()
+}
+
 fn number_to_identifier(num: &str) -> String {
     num.chars()
         .map(|x| match x {
             '.' => 'd',
             '0'..='9' => x,
             '-' => 'n',
-            _ => panic!("This is not a valid number. Contains unknown sign {}", x),
+            // Exponent of floats such as `1e10`
+            'e' | 'E' => 'e',
+            _ => '_',
         })
         .collect()
 }
 
-fn string_to_identifier(num: &str) -> String {
+pub fn string_to_identifier(num: &str) -> String {
     num.chars()
         .map(|x| match x {
             '0'..='9' => x.to_string(),
@@ -56,7 +184,13 @@ fn string_to_identifier(num: &str) -> String {
             '|' => "_vertical_bar".to_string(),
             '}' => "_right_brace".to_string(),
             '~' => "_tilde".to_string(),
-            _ => '_'.to_string(),
+            // Other scripts are transliterated. Remaining symbols such as emojis are named by their
+            // code point.
+            x if x.is_whitespace() || x.is_ascii() => '_'.to_string(),
+            x => match naming::transliterate(x) {
+                Some(ascii) => ascii.to_string(),
+                None => format!("_u{:x}", u32::from(x)),
+            },
         })
         .collect()
 }
@@ -1,5 +1,7 @@
 extern crate ntest_test_cases;
-use ntest_test_cases::test_case;
+use ntest_test_cases::{
+    test_case, test_case_types, test_cases_from, test_files, test_matrix, test_pairwise,
+};
 
 #[test_case(42)]
 fn one_arg(x: u32) {
@@ -67,3 +69,231 @@ fn return_result(x: u32) -> core::result::Result<(), ()> {
     assert_eq!(x, 42);
     Ok(())
 }
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[test_case((1, 2), [1, 2], vec![1, 2])]
+#[test_case((3, 4), [3, 4], vec![3, 4])]
+fn collections(tuple: (i32, i32), array: [i32; 2], vector: Vec<i32>) {
+    assert_eq!([tuple.0, tuple.1], array);
+    assert_eq!(array.to_vec(), vector);
+}
+
+#[test_case(Point { x: 1, y: 2 }, Some(3))]
+#[test_case(Point { x: -1, y: 1 }, None)]
+fn struct_literal(point: Point, sum: Option<i32>) {
+    assert_eq!(Some(point.x + point.y).filter(|sum| *sum != 0), sum);
+}
+
+#[test_case(std::time::Duration::from_millis(5), 5)]
+#[test_case(std::time::Duration::from_secs("1".parse().unwrap()), 1000)]
+fn method_call(duration: std::time::Duration, millis: u128) {
+    assert_eq!(duration.as_millis(), millis);
+}
+
+#[test_case(|x| x + 1, 2)]
+#[test_case(|x| x * 3, 3)]
+fn closure(f: fn(i32) -> i32, expected: i32) {
+    assert_eq!(f(1), expected);
+}
+
+#[test_case(2, 3 => 5)]
+#[test_case(-1, 1 => 0)]
+#[test_case(2, 2 => 5)]
+#[should_panic(expected = "test case arguments: 2, 2")]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[test_case("42" => matches Ok(42))]
+#[test_case("4a" => matches Err(_))]
+#[test_case("7" => matches Ok(x) if x % 7 == 0)]
+#[test_case("8" => matches Ok(x) if x % 7 == 0)]
+#[should_panic(expected = "assertion failed: `Ok(8)` does not match `Ok(x) if x % 7 == 0`")]
+fn parse(input: &str) -> Result<u32, std::num::ParseIntError> {
+    let value = input.parse()?;
+    Ok(value)
+}
+
+#[test_case(vec![3, 1, 2] => vec![1, 2, 3], name = "sorted")]
+fn sort(values: Vec<u32>) -> Vec<u32> {
+    let mut values = values;
+    values.sort();
+    values
+}
+
+#[test_case(3; "three is prime")]
+#[test_case(0, panics = "not defined for zero")]
+#[test_case(4, ignore = "four is not prime")]
+#[test_case(9; "nine")]
+#[should_panic(expected = "9 is not prime")]
+fn is_prime(x: u32) {
+    assert!(x > 0, "not defined for zero");
+    assert!([2, 3, 5, 7].contains(&x), "{} is not prime", x);
+}
+
+#[test_case(8 => 4; "halved")]
+fn half(x: u32) -> u32 {
+    x / 2
+}
+
+#[test_cases_from("tests/data/parse.csv", name = "input")]
+fn parse_csv(input: &str, expected: Result<u32, ()>) {
+    assert_eq!(input.parse::<u32>().map_err(|_| ()), expected);
+}
+
+#[test_cases_from("tests/data/sum.json")]
+fn sum_json(summands: Vec<i64>, sum: i64, limit: Option<i64>) {
+    assert_eq!(summands.iter().sum::<i64>(), sum);
+    assert!(limit.is_none_or(|limit| sum <= limit));
+}
+
+#[test_cases_from("tests/data/greet.toml", name = "name")]
+fn greet_toml(name: String, greeting: &str) {
+    assert_eq!(format!("Hello {}!", name), greeting);
+}
+
+#[test_files("tests/fixtures/**/*.json")]
+fn fixture_is_array(path: &std::path::Path, contents: &str) {
+    assert_eq!(path.extension().unwrap(), "json");
+    assert!(contents.trim().starts_with('[') && contents.trim().ends_with(']'));
+}
+
+#[test_files("tests/fixtures/numbers/*.json")]
+fn fixture_exists(path: &std::path::Path) {
+    assert!(path.is_file());
+}
+
+#[test_matrix([1, 2, 4], ["utf8", "latin1"], [true, false])]
+#[test_case(8, "ascii", true)]
+fn matrix(size: usize, encoding: &str, strict: bool) {
+    assert!(size.is_power_of_two());
+    assert!(encoding.len() >= 4);
+    assert!(strict || size < 8);
+}
+
+#[test_case(0, 4)]
+#[test_matrix([1, 3], [5, 7])]
+#[should_panic(expected = "odd")]
+#[test_matrix([2], [4, 6])]
+fn matrix_with_attributes(x: u32, y: u32) {
+    assert!(y > x);
+    assert!([0, 2, 4].contains(&x), "odd");
+}
+
+#[test_pairwise(["ext4", "btrfs", "xfs"], [512, 4096], [true, false], [1, 2, 4, 8])]
+fn pairwise(fs: &str, block_size: u32, journal: bool, threads: u32) {
+    assert!(fs.len() >= 3);
+    assert!(block_size.is_power_of_two() && threads.is_power_of_two());
+    assert!(journal || fs != "ext3");
+}
+
+#[test_pairwise([1, 2], [3, 4], [5, 6], [7, 8], strength = 3)]
+#[test_case(0, 0, 0, 0)]
+fn pairwise_triples(a: u32, b: u32, c: u32, d: u32) {
+    assert!(a <= b && c <= d);
+}
+
+trait Codec: Sized {
+    fn encode(&self) -> Vec<u8>;
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_codec {
+    ($($ty:ty),*) => {$(
+        impl Codec for $ty {
+            fn encode(&self) -> Vec<u8> {
+                self.to_le_bytes().to_vec()
+            }
+
+            fn decode(bytes: &[u8]) -> Self {
+                let mut le_bytes = [0; std::mem::size_of::<$ty>()];
+                le_bytes.copy_from_slice(bytes);
+                <$ty>::from_le_bytes(le_bytes)
+            }
+        }
+    )*};
+}
+
+impl_codec!(u8, u16, u32, i64);
+
+#[derive(Debug, Default, PartialEq)]
+struct MyInt(u16);
+
+impl Codec for MyInt {
+    fn encode(&self) -> Vec<u8> {
+        self.0.encode()
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        MyInt(u16::decode(bytes))
+    }
+}
+
+#[test_case_types(u8, u16, MyInt)]
+fn roundtrip<T: Codec + Default + PartialEq + std::fmt::Debug>() {
+    assert_eq!(T::decode(&T::default().encode()), T::default());
+}
+
+#[test_case(5 => 4, type = u32)]
+#[test_case(250 => 8, type = i64)]
+#[test_case(7 => 2)]
+#[test_case_types(u16)]
+fn encoded_size<T: Codec + From<u8>>(x: u8) -> usize {
+    let value = T::from(x);
+    T::decode(&value.encode()).encode().len()
+}
+
+#[test_case_types((u8, u16), (u32, i64))]
+#[test_case(1)]
+fn widen<S: Codec + From<u8>, L: Codec + From<S>>(x: u8) {
+    let small = S::from(x);
+    assert!(L::from(small).encode().len() > 1);
+}
+
+mod colors {
+    pub const RED: u32 = 0xff0000;
+}
+
+mod fruits {
+    pub const RED: u32 = 0xff2222;
+}
+
+#[test_case(colors::RED)]
+#[test_case(fruits::RED)]
+fn same_last_path_segment(x: u32) {
+    assert!(x >= 0xff0000);
+}
+
+#[test_case('a', b'a', b"ab")]
+#[test_case('€', b'\n', b"\x00")]
+fn char_and_byte_literals(c: char, byte: u8, bytes: &[u8]) {
+    assert!(c.len_utf8() >= 1 && byte > 0 && !bytes.is_empty());
+}
+
+#[test_case("grüße")]
+#[test_case("привет")]
+#[test_case("日本")]
+fn unicode_strings(x: &str) {
+    assert!(!x.is_ascii());
+}
+
+#[test_case("42", "u32", name = "parse_{input}_as_{kind}")]
+#[test_case("-7", "i64", name = "parse_{input}_as_{kind}")]
+fn name_template(input: &str, kind: &str) {
+    match kind {
+        "u32" => assert!(input.parse::<u32>().is_ok()),
+        _ => assert!(input.parse::<i64>().is_ok()),
+    }
+}
+
+#[test_case(
+    "a very long argument which would make the name of the test hard to read in the output"
+)]
+fn long_argument(x: &str) {
+    assert!(x.len() > 64);
+}
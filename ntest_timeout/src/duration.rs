@@ -0,0 +1,125 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
+const UNITS: &[(&str, u128)] = &[
+    ("ns", 1),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60_000_000_000),
+    ("h", 3_600_000_000_000),
+];
+
+/// Parse a human readable duration such as `250ms`, `2s`, `1.5s` or `1m30s`.
+///
+/// A duration is a sequence of numbers each followed by one of the units
+/// `ns`, `us` (or `µs`), `ms`, `s`, `m`, or `h`. Whitespace between the parts is ignored.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let mut rest = input.trim();
+    if rest.is_empty() {
+        return Err("Empty duration. Example: \"250ms\", \"2s\", or \"1m30s\"".to_string());
+    }
+    let mut total_nanos: u128 = 0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number = &rest[..number_len];
+        if number.is_empty() {
+            return Err(format!(
+                "Expected a number at '{}' in duration \"{}\"",
+                rest, input
+            ));
+        }
+        rest = rest[number_len..].trim_start();
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = rest[unit_len..].trim_start();
+        let unit_nanos = match UNITS.iter().find(|(name, _)| *name == unit) {
+            Some((_, nanos)) => *nanos,
+            None if unit.is_empty() => {
+                return Err(format!(
+                "Missing unit after '{}' in duration \"{}\". Expected one of ns, us, ms, s, m, h",
+                number, input
+            ))
+            }
+            None => {
+                return Err(format!(
+                    "Unknown unit '{}' in duration \"{}\". Expected one of ns, us, ms, s, m, h",
+                    unit, input
+                ))
+            }
+        };
+        let nanos = number_to_nanos(number, unit_nanos)
+            .ok_or_else(|| format!("Invalid number '{}' in duration \"{}\"", number, input))?;
+        total_nanos = total_nanos
+            .checked_add(nanos)
+            .ok_or_else(|| format!("Duration \"{}\" is too large", input))?;
+    }
+    let secs = u64::try_from(total_nanos / 1_000_000_000)
+        .map_err(|_| format!("Duration \"{}\" is too large", input))?;
+    Ok(Duration::new(secs, (total_nanos % 1_000_000_000) as u32))
+}
+
+fn number_to_nanos(number: &str, unit_nanos: u128) -> Option<u128> {
+    let (integer, fraction) = match number.find('.') {
+        Some(pos) => (&number[..pos], &number[pos + 1..]),
+        None => (number, ""),
+    };
+    if integer.is_empty() || fraction.contains('.') || (number.contains('.') && fraction.is_empty())
+    {
+        return None;
+    }
+    let mut nanos = integer.parse::<u128>().ok()?.checked_mul(unit_nanos)?;
+    let mut scale = unit_nanos;
+    for digit in fraction.chars() {
+        scale /= 10;
+        nanos = nanos.checked_add(u128::from(digit.to_digit(10)?) * scale)?;
+    }
+    Some(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_units() {
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("3m"), Ok(Duration::from_secs(180)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("7us"), Ok(Duration::from_micros(7)));
+        assert_eq!(parse_duration("7µs"), Ok(Duration::from_micros(7)));
+        assert_eq!(parse_duration("9ns"), Ok(Duration::from_nanos(9)));
+    }
+
+    #[test]
+    fn combined_units() {
+        assert_eq!(parse_duration("1m30s"), Ok(Duration::from_secs(90)));
+        assert_eq!(
+            parse_duration("1m 30s 5ms"),
+            Ok(Duration::from_millis(90_005))
+        );
+    }
+
+    #[test]
+    fn fractions() {
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("0.25ms"), Ok(Duration::from_micros(250)));
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("250").is_err());
+        assert!(parse_duration("ms").is_err());
+        assert!(parse_duration("5sec").is_err());
+        assert!(parse_duration("1..5s").is_err());
+        assert!(parse_duration("1.s").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+}
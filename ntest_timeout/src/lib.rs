@@ -3,131 +3,1117 @@
 extern crate proc_macro;
 extern crate syn;
 
+use ntest_proc_macro_helper::{check_errors, combine_errors, ntest_path, path_ends_with};
 use proc_macro::TokenStream;
-use quote::quote;
+use proc_macro2::Span;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 
-use syn::parse_macro_input;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+mod duration;
+
+// Native async timeouts are only generated if one of the async runtime features is enabled
+const ASYNC_RUNTIME: bool = cfg!(any(
+    feature = "tokio",
+    feature = "async-std",
+    feature = "smol"
+));
 
 /// The timeout attribute can be used for tests to let them fail if they exceed a certain execution time.
-/// With the `#[timeout]` attribute a timeout in milliseconds is added to a test.
+/// With the `#[timeout]` attribute a timeout is added to a test.
+///
+/// The timeout can be given as:
+///
+/// * an integer in milliseconds. For example `#[timeout(10)]` will fail if the test takes longer than 10 milliseconds.
+/// * a duration string such as `#[timeout("250ms")]`, `#[timeout("2s")]`, or `#[timeout("1m30s")]`.
+///   Supported units are `ns`, `us`, `ms`, `s`, `m`, and `h`. The string is validated at compile time.
+/// * a constant expression of type `std::time::Duration`, for example `#[timeout(MY_BUDGET)]`
+///   or `#[timeout(Duration::from_secs(2))]`.
+///
+/// A soft timeout prints a warning with the test name and the elapsed time if the test takes longer,
+/// but the test still passes. It can be combined with the hard timeout which lets the test fail:
+/// `#[timeout(warn = "200ms", fail = "1s")]` or `#[timeout(1000, warn = 200)]`. The soft timeout must
+/// be shorter than the hard timeout.
+/// If no soft timeout is given, the warn threshold of the `RUST_TEST_TIME_UNIT` or `RUST_TEST_TIME_INTEGRATION`
+/// environment variables of libtest is used. For example `RUST_TEST_TIME_UNIT=200,1000` prints a warning
+/// for unit tests with a timeout attribute which take longer than 200 milliseconds.
+///
+/// A CPU time limit such as `#[timeout(cpu = "500ms")]` fails the test only if the test thread itself
+/// computes longer. Time the thread spends sleeping, blocking, or waiting for a busy runner is not
+/// counted. The CPU time is measured on Linux with the `CLOCK_THREAD_CPUTIME_ID` clock of the test
+/// thread. A wall-clock limit of ten times the CPU time limit stops tests which block forever. It can
+/// be set explicitly with `#[timeout(fail = "2s", cpu = "500ms")]`. Timeout messages and reports
+/// contain both the elapsed and the CPU time. On other platforms the CPU time limit is applied to the
+/// wall-clock time. CPU time limits are not supported on async functions.
+///
+/// The timeout can be adjusted at runtime with environment variables. This is useful for slow CI runners
+/// or tools such as valgrind:
+///
+/// * `NTEST_TIMEOUT_SCALE=3` multiplies all timeouts by the given factor.
+/// * `NTEST_TIMEOUT=off` disables all timeouts.
+///
+/// Invalid values are ignored with a warning.
 ///
-/// The function input must be of type `int`. For example `#[timeout(10)]` will fail if the test takes longer than 10 milliseconds.
+/// On Linux timeouts are suspended while a debugger or another tracer is attached to the test process.
+///
+/// By default the test runs in a separate thread which can not be killed on timeout. Instead the
+/// cancellation token of the thread is cancelled and the thread gets a short grace period to stop.
+/// Long running test code should poll `ntest::cancellation()` or call `ntest::check_cancelled()`
+/// to stop on timeout. Otherwise the thread keeps running in the background. If one of the
+/// `tokio`, `async-std`, or `smol` features of `ntest` is enabled, async test functions are instead
+/// raced against a timer which runs on a helper thread and works with every runtime if `#[timeout]`
+/// is placed above the test attribute of the runtime. A timed out future is dropped and thereby
+/// cancelled. Futures which block the executor thread can not be interrupted in this mode. Placed
+/// below `#[tokio::test]` or `#[async_std::test]`, the test runs with its runtime in a separate
+/// thread like a synchronous test, which also stops blocking code.
+///
+/// The attribute can also guard helper functions with parameters, generic functions, methods, and
+/// associated functions which use `Self`. Such functions run in a scoped thread since they may borrow
+/// data of the caller. Their arguments
+/// and return value must be `Send`. A scoped thread can not be left running in the background.
+/// If it ignores the cancellation after a timeout, the timeout is printed right away and the test
+/// fails once the thread has finished.
+///
+/// On Linux `NTEST_TIMEOUT_BACKTRACE=1` adds the stack backtrace of the hung test thread to the
+/// timeout message of a threaded test. Only that thread is interrupted. `NTEST_TIMEOUT_BACKTRACE=all`
+/// adds the stack backtraces of all other threads of the process as well, which shows both sides of
+/// a deadlock. The threads are interrupted with the `SIGRTMIN+2` signal. A handler the program
+/// installed for it is called for other signals and restored afterwards. The frames are symbolized
+/// with `addr2line` if it is installed.
+///
+/// If `NTEST_REPORT` is set to a path, a JSON object is appended to that file for every run of a test
+/// with a timeout. It contains the test `name`, the `outcome` (`pass`, `fail`, or `timeout`),
+/// `elapsed_ms`, the effective `budget_ms`, and the panic `message`. Failing `ntest` assertion macros
+/// append records as well. If one fails within a test with a timeout, its record is the only one of
+/// the test. A test which returns `Result::Err` is recorded as `fail` with the error
+/// as `message`.
+///
+/// `#[timeout(1000, retries = 2)]` runs a failed or timed out test up to two more times. Every attempt
+/// gets the full timeout. See the `#[retry]` attribute for details.
 ///
 /// # Examples
 ///
 /// This example will not panic
 ///
-/// ```
-/// #[test]
+/// ```no_run
+/// # use ntest::timeout;
+/// # use std::{thread, time};
 /// #[timeout(100)]
 /// fn no_timeout() {
 ///     let fifty_millis = time::Duration::from_millis(50);
 ///     thread::sleep(fifty_millis);
 /// }
+/// # fn main() {
+/// #     no_timeout();
+/// # }
 /// ```
 ///
 /// This example will panic and break the infinite loop after 10 milliseconds.
 ///
-/// ```
-/// #[test]
+/// ```no_run
+/// # use ntest::timeout;
 /// #[timeout(10)]
-/// #[should_panic]
 /// fn timeout() {
 ///     loop {};
 /// }
+/// # fn main() {
+/// #     timeout();
+/// # }
 /// ```
 ///
 /// Also works with test functions using a Result:
 ///
-/// ```
-/// #[test]
+/// ```no_run
+/// # use ntest::timeout;
+/// # use std::{thread, time};
 /// #[timeout(100)]
 /// fn timeout_with_result() -> Result<(), String> {
 ///     let ten_millis = time::Duration::from_millis(10);
 ///     thread::sleep(ten_millis);
 ///     Ok(())
 /// }
+/// # fn main() {
+/// #     timeout_with_result().unwrap();
+/// # }
+/// ```
+///
+/// Durations can be written in a human readable form or as a constant:
+///
+/// ```no_run
+/// # use ntest::timeout;
+/// # use std::time::Duration;
+/// const BUDGET: Duration = Duration::from_secs(90);
+///
+/// #[test]
+/// #[timeout("1m30s")]
+/// fn long_running() {}
+///
+/// #[test]
+/// #[timeout(BUDGET)]
+/// fn long_running_with_constant() {}
+/// ```
+///
+/// Warn if the test takes longer than 200 milliseconds and fail after one second:
+///
+/// ```no_run
+/// # use ntest::timeout;
+/// # use std::{thread, time::Duration};
+/// #[test]
+/// #[timeout(warn = "200ms", fail = "1s")]
+/// fn soft_timeout() {
+///     thread::sleep(Duration::from_millis(300));
+/// }
+/// ```
+///
+/// The timeout attribute can also be placed on an inline module. It is added to all functions with a
+/// `#[test]`, `#[test_case]`, `#[test_matrix]`, `#[test_pairwise]`, or async runtime test attribute such as `#[tokio::test]` within the module
+/// and its inline submodules. A timeout attribute on a function or submodule overrides the timeout
+/// of the module. A timeout given as a constant expression is not applied to submodules since it may
+/// not be in scope there. Such submodules need their own timeout attribute:
+///
+/// ```no_run
+/// # use ntest::timeout;
+/// #[cfg(test)]
+/// #[timeout(5000)]
+/// mod tests {
+///     #[test]
+///     fn with_module_timeout() {}
+///
+///     #[test]
+///     #[timeout(100)]
+///     fn with_own_timeout() {}
+/// }
+/// ```
+///
+/// Async tests with the `tokio` feature of `ntest` enabled:
+///
+/// ```ignore
+/// #[timeout(100)]
+/// #[tokio::test]
+/// async fn async_timeout() {
+///     tokio::time::sleep(Duration::from_millis(10)).await;
+/// }
+/// ```
+///
+/// Generic helper functions with parameters:
+///
+/// ```
+/// # use ntest::timeout;
+/// # trait Codec: Sized {
+/// #     fn encode(&self) -> Vec<u8>;
+/// #     fn decode(bytes: &[u8]) -> Self;
+/// # }
+/// #[timeout(100)]
+/// fn roundtrip<T: Codec + Send + Sync>(value: &T) -> T {
+///     T::decode(&value.encode())
+/// }
 /// ```
 #[proc_macro_attribute]
 pub fn timeout(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = syn::parse_macro_input!(item as syn::ItemFn);
-    let time_ms = get_timeout(&parse_macro_input!(attr as syn::AttributeArgs));
+    expand_timeout(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_timeout(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let args = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated.parse2(attr)?;
+    let mut errors = vec![];
+    let mut timeout_args = parse_timeout_args(&args, &mut errors);
+    let mut input = match syn::parse2::<syn::Item>(item) {
+        Ok(syn::Item::Fn(input)) => input,
+        Ok(syn::Item::Mod(mut module)) => {
+            let items = match &mut module.content {
+                Some((_, items)) => items,
+                None => {
+                    errors.push(syn::Error::new_spanned(
+                        &module,
+                        "The timeout attribute is only allowed on inline modules",
+                    ));
+                    return Err(combine_errors(errors));
+                }
+            };
+            if let Some(expr) = non_literal_argument(&args) {
+                check_nested_modules(items, expr, &mut errors);
+            }
+            check_errors(errors)?;
+            let ntest = ntest_path()?;
+            let timeout_attribute: syn::Attribute = syn::parse_quote! { #[#ntest::timeout(#args)] };
+            add_module_timeout(items, &timeout_attribute);
+            return Ok(quote! { #module });
+        }
+        Ok(item) => {
+            errors.push(syn::Error::new_spanned(
+                item,
+                "The timeout attribute is only allowed on functions and inline modules",
+            ));
+            return Err(combine_errors(errors));
+        }
+        Err(e) => {
+            errors.push(e);
+            return Err(combine_errors(errors));
+        }
+    };
+    check_other_attributes(&input, &mut errors);
+    // A retry attribute below the timeout attribute is merged into the timeout arguments
+    let mut retry_attributes = take_attributes(&mut input.attrs, is_retry_attribute).into_iter();
+    if let Some(attribute) = retry_attributes.next() {
+        match attribute
+            .parse_args::<syn::Expr>()
+            .and_then(|expr| get_retries(&expr))
+        {
+            Ok(_) if timeout_args.retries.is_some() => errors.push(syn::Error::new_spanned(
+                &attribute,
+                "Retries are given by the timeout attribute and the retry attribute. Remove one of them.",
+            )),
+            Ok(retries) => timeout_args.retries = Some(retries),
+            Err(e) => errors.push(e),
+        }
+    }
+    for attribute in retry_attributes {
+        errors.push(syn::Error::new_spanned(
+            attribute,
+            "Retry attribute is only allowed once",
+        ));
+    }
+    expand_function(input, timeout_args, errors)
+}
+
+// Wraps the body of the function in the timeout and retry helpers of the runtime
+fn expand_function(
+    input: syn::ItemFn,
+    timeout_args: TimeoutArgs,
+    mut errors: Vec<syn::Error>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let vis = &input.vis;
+    let sig = &input.sig;
+    let output = &sig.output;
+    let body = &input.block;
+    let attrs = &input.attrs;
+    let retries = timeout_args.retries.is_some();
+    if let Some(asyncness) = &sig.asyncness {
+        if !ASYNC_RUNTIME {
+            errors.push(syn::Error::new_spanned(asyncness, "Timeout for async functions requires one of the `tokio`, `async-std`, or `smol` features. Otherwise place #[timeout] below the test attribute of the async runtime."));
+        }
+        if let Some(cpu) = &timeout_args.cpu {
+            errors.push(syn::Error::new_spanned(cpu, "CPU time timeouts are not supported for async functions. Place #[timeout] below the test attribute of the async runtime to measure the CPU time of the executor thread."));
+        }
+        if retries && !sig.inputs.is_empty() {
+            errors.push(syn::Error::new_spanned(&sig.inputs, "Retries of async functions are only supported without parameters. Place the attribute below the test attribute of the async runtime instead."));
+        }
+    }
+    check_errors(errors)?;
+    let ntest = ntest_path()?;
+    let timeout = timeout_spec(&timeout_args, &sig.ident, &ntest);
+    let failure = output_failure(&ntest, output);
+    let result = if sig.asyncness.is_some() && retries {
+        // Every attempt polls a new future
+        quote! {
+            #(#attrs)*
+            #vis #sig {
+                #ntest::execute_future_with_retries(#timeout, || async move #body, #failure).await
+            }
+        }
+    } else if sig.asyncness.is_some() {
+        quote! {
+            #(#attrs)*
+            #vis #sig {
+                #ntest::execute_future_with_timeout(#timeout, async move #body, #failure).await
+            }
+        }
+    } else if sig.inputs.is_empty()
+        && sig.generics.params.is_empty()
+        && !mentions(quote! { #sig #body }, "Self")
+    {
+        quote! {
+            #(#attrs)*
+            #vis #sig {
+                fn ntest_callback() #output
+                #body
+                #ntest::execute_test_with_timeout(#timeout, ntest_callback, #failure)
+            }
+        }
+    } else {
+        // Parameters, `self`, generics, and lifetimes of the function are captured by a closure
+        // which runs in a scoped thread since it may borrow data of the caller. Associated functions
+        // which use `Self` can not be moved into a nested function either.
+        let output = match output {
+            syn::ReturnType::Type(_, ty) if !matches!(**ty, syn::Type::ImplTrait(_)) => {
+                quote! { -> #ty }
+            }
+            _ => quote! {},
+        };
+        // The closure is called once per attempt. It must not consume captured data with retries.
+        let execute = if retries {
+            quote! { execute_scoped_test_with_retries }
+        } else {
+            quote! { execute_scoped_test_with_timeout }
+        };
+        quote! {
+            #(#attrs)*
+            #vis #sig {
+                #ntest::#execute(#timeout, move || #output #body, #failure)
+            }
+        }
+    };
+    Ok(result)
+}
+
+// Finds keywords such as `Self`, which is not available in a nested function
+fn mentions(tokens: proc_macro2::TokenStream, keyword: &str) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == keyword,
+        proc_macro2::TokenTree::Group(group) => mentions(group.stream(), keyword),
+        _ => false,
+    })
+}
+
+/// Runs a block with its own deadline and panics if it does not finish in time.
+///
+/// The timeout is given like for the `#[timeout]` attribute as integer in milliseconds, duration string,
+/// or `Duration` expression. The block runs in a separate thread which may borrow local variables.
+/// It evaluates to the value of the block. `return` and `?` within the block apply to the block only.
+/// On timeout the thread is cancelled like a test with the `#[timeout]` attribute. The cancellation
+/// is cooperative. If the block ignores it, the timeout is printed right away and the panic follows
+/// once the thread has finished since it borrows data of the test.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use ntest::within;
+/// #[test]
+/// fn steps() {
+///     let mut connection = connect();
+///     let response = within!("200ms", { connection.send("ping") });
+///     within!(500, {
+///         connection.close();
+///     });
+/// }
+/// ```
+#[proc_macro]
+pub fn within(input: TokenStream) -> TokenStream {
+    expand_within(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct WithinArgs {
+    timeout: syn::Expr,
+    body: syn::Expr,
+}
+
+impl syn::parse::Parse for WithinArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let timeout = input.parse()?;
+        input.parse::<syn::Token![,]>()?;
+        let body = input.parse()?;
+        input.parse::<Option<syn::Token![,]>>()?;
+        Ok(WithinArgs { timeout, body })
+    }
+}
+
+fn expand_within(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let args: WithinArgs = syn::parse2(input)?;
+    let timeout = get_timeout(&args.timeout)?;
+    let body = &args.body;
+    let ntest = ntest_path()?;
+    Ok(quote! {
+        #ntest::execute_within(#timeout, || #body)
+    })
+}
+
+/// The retry attribute runs a failing test again.
+///
+/// `#[retry(3)]` runs the test up to three more times if it panics or returns `Result::Err`. The
+/// test passes as soon as one attempt passes. If an attempt passes after others failed, a warning
+/// with the outcome and duration of every attempt is printed. If all attempts fail, the test fails
+/// with the outcome, duration, and panic message or error of every attempt. Every attempt is recorded in the `NTEST_REPORT` file.
+///
+/// Combined with the `#[timeout]` attribute every attempt gets the full timeout and timed out attempts
+/// are retried as well. This is the same as `#[timeout(1000, retries = 3)]`. Retried test functions
+/// with parameters must not consume them since the body is called once per attempt.
+///
+/// Set `NTEST_RETRIES=off` to run every test only once so that flaky tests stay visible.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use ntest::{retry, timeout};
+/// #[test]
+/// #[retry(3)]
+/// fn flaky_network_loopback() {
+///     assert_eq!(echo("ping"), "ping");
+/// }
+///
+/// #[test]
+/// #[timeout(1000, retries = 2)]
+/// fn flaky_with_timeout() {
+///     assert_eq!(echo("ping"), "ping");
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn retry(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_retry(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_retry(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut errors = vec![];
+    let retries = if attr.is_empty() {
+        Err(syn::Error::new(
+            Span::call_site(),
+            "Number of retries expected. Example: #[retry(3)]",
+        ))
+    } else {
+        syn::parse2::<syn::Expr>(attr).and_then(|expr| get_retries(&expr).map(|n| (expr, n)))
+    };
+    let mut input = match syn::parse2::<syn::ItemFn>(item) {
+        Ok(input) => input,
+        Err(e) => {
+            errors.extend(retries.err());
+            errors.push(e);
+            return Err(combine_errors(errors));
+        }
+    };
+    // A timeout attribute below the retry attribute is merged into the arguments
+    let mut timeout_attributes =
+        take_attributes(&mut input.attrs, is_timeout_attribute).into_iter();
+    let mut timeout_args = match timeout_attributes.next() {
+        Some(attribute) => match attribute
+            .parse_args_with(Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated)
+        {
+            Ok(args) => parse_timeout_args(&args, &mut errors),
+            Err(e) => {
+                errors.push(e);
+                TimeoutArgs::default()
+            }
+        },
+        None => TimeoutArgs::default(),
+    };
+    for attribute in timeout_attributes {
+        errors.push(syn::Error::new_spanned(
+            attribute,
+            "Timeout attribute is only allowed once",
+        ));
+    }
+    match retries {
+        Ok((expr, _)) if timeout_args.retries.is_some() => errors.push(syn::Error::new_spanned(
+            expr,
+            "Retries are given by the timeout attribute and the retry attribute. Remove one of them.",
+        )),
+        Ok((_, retries)) => timeout_args.retries = Some(retries),
+        Err(e) => errors.push(e),
+    }
+    expand_function(input, timeout_args, errors)
+}
+
+/// The isolated attribute runs a test in a separate process.
+///
+/// The test binary is executed again with an exact filter for the annotated test. The child process
+/// runs in its own process group. Its output and outcome are relayed to the test in the parent process.
+/// This makes it possible to test code which aborts, overflows the stack, calls `std::process::exit`,
+/// or corrupts global state.
+///
+/// Combined with the `#[timeout]` attribute the whole process group of the child is killed on timeout.
+/// Processes started by the test are killed as well once the test is done.
+///
+/// Isolated tests must be part of a test binary using the default libtest harness. Supported return
+/// types are `()` and `Result<(), E>`.
+///
+/// # Examples
+///
+/// This test will fail even though the process is aborted:
+///
+/// ```no_run
+/// # use ntest::isolated;
+/// #[test]
+/// #[isolated]
+/// #[should_panic]
+/// fn aborts() {
+///     std::process::abort();
+/// }
+/// ```
+///
+/// The child process is killed after 100 milliseconds:
+///
+/// ```no_run
+/// # use ntest::{isolated, timeout};
+/// #[test]
+/// #[isolated]
+/// #[timeout(100)]
+/// #[should_panic]
+/// fn killed_on_timeout() {
+///     loop {}
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn isolated(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand_isolated(attr.into(), item.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_isolated(
+    attr: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut errors = vec![];
+    if !attr.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            attr,
+            "The isolated attribute does not take any arguments. Example: #[isolated]",
+        ));
+    }
+    let input = match syn::parse2::<syn::ItemFn>(item) {
+        Ok(input) => input,
+        Err(e) => {
+            errors.push(e);
+            return Err(combine_errors(errors));
+        }
+    };
     let vis = &input.vis;
     let sig = &input.sig;
+    let ident = &sig.ident;
     let output = &sig.output;
     let body = &input.block;
     let attrs = &input.attrs;
-    check_other_attributes(&input);
-    let result = quote! {
+    if let Some(asyncness) = &sig.asyncness {
+        errors.push(syn::Error::new_spanned(
+            asyncness,
+            "The isolated attribute must be placed below the test attribute of the async runtime.",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            &sig.generics,
+            "The isolated attribute is only allowed on test functions without generic parameters.",
+        ));
+    }
+    if !sig.inputs.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            &sig.inputs,
+            "The isolated attribute is only allowed on test functions without parameters.",
+        ));
+    }
+    check_errors(errors)?;
+    let ntest = ntest_path()?;
+    Ok(quote! {
         #(#attrs)*
         #vis #sig {
-            fn ntest_callback() #output
+            fn ntest_isolated_callback() #output
             #body
-            let ntest_timeout_now = std::time::Instant::now();
-            
-            type NtestPanicPayload = std::boxed::Box<dyn std::any::Any + std::marker::Send + 'static>;
-            // Channel sends Result: Ok for success, Err for panic payload
-            let (sender, receiver) = std::sync::mpsc::channel::<std::result::Result<_, NtestPanicPayload>>();
-            std::thread::spawn(move || {
-                let panic_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    ntest_callback()
-                }));
-                // Send will fail if receiver has already timed out or dropped - this is expected
-                let _ = sender.send(panic_result);
-            });
-            match receiver.recv_timeout(std::time::Duration::from_millis(#time_ms)) {
-                std::result::Result::Ok(std::result::Result::Ok(t)) => return t,
-                std::result::Result::Ok(std::result::Result::Err(panic_payload)) => {
-                    // Resume the panic with the original payload to preserve panic message
-                    std::panic::resume_unwind(panic_payload);
-                },
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => panic!("timeout: the function call took {} ms. Max time {} ms", ntest_timeout_now.elapsed().as_millis(), #time_ms),
-                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => panic!("Thread disconnected unexpectedly"),
-            }
+            #ntest::execute_isolated(concat!(module_path!(), "::", stringify!(#ident)), ntest_isolated_callback)
         }
+    })
+}
+
+fn check_other_attributes(input: &syn::ItemFn, errors: &mut Vec<syn::Error>) {
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| is_timeout_attribute(attribute))
+    {
+        errors.push(syn::Error::new_spanned(
+            attribute,
+            "Timeout attribute is only allowed once",
+        ));
+    }
+}
+
+// Removes the attributes which match from the function and returns them
+fn take_attributes(
+    attrs: &mut Vec<syn::Attribute>,
+    matches: fn(&syn::Attribute) -> bool,
+) -> Vec<syn::Attribute> {
+    let (taken, kept) = attrs.drain(..).partition(matches);
+    *attrs = kept;
+    taken
+}
+
+fn is_retry_attribute(attribute: &syn::Attribute) -> bool {
+    path_ends_with(&attribute.path, "retry")
+}
+
+fn is_timeout_attribute(attribute: &syn::Attribute) -> bool {
+    path_ends_with(&attribute.path, "timeout")
+}
+
+// Matches `#[test]` as well as the test attributes of async runtimes such as `#[tokio::test]`
+fn is_test_attribute(attribute: &syn::Attribute) -> bool {
+    path_ends_with(&attribute.path, "test")
+}
+
+// Matches `#[test_case]`, `#[test_matrix]`, and `#[test_pairwise]` which define test cases
+fn is_test_case_attribute(attribute: &syn::Attribute) -> bool {
+    ["test_case", "test_matrix", "test_pairwise"]
+        .iter()
+        .any(|ident| path_ends_with(&attribute.path, ident))
+}
+
+fn is_test_case_types_attribute(attribute: &syn::Attribute) -> bool {
+    path_ends_with(&attribute.path, "test_case_types")
+}
+
+fn is_test_case_timeout_attribute(attribute: &syn::Attribute) -> bool {
+    path_ends_with(&attribute.path, "test_case_timeout")
+}
+
+// Matches test cases with a timeout keyword such as `#[test_case(42, timeout = "2s")]`. The tokens
+// are scanned since test cases may contain an expected result such as `#[test_case(1 => 2)]`.
+fn test_case_has_timeout(attribute: &syn::Attribute) -> bool {
+    let tokens = match attribute.tokens.clone().into_iter().next() {
+        Some(proc_macro2::TokenTree::Group(group)) => group.stream(),
+        _ => return false,
     };
-    result.into()
+    let tokens: Vec<proc_macro2::TokenTree> = tokens.into_iter().collect();
+    tokens.windows(2).any(|window| match window {
+        [proc_macro2::TokenTree::Ident(ident), proc_macro2::TokenTree::Punct(punct)] => {
+            ident == "timeout"
+                && punct.as_char() == '='
+                && punct.spacing() == proc_macro2::Spacing::Alone
+        }
+        _ => false,
+    })
 }
 
-fn check_other_attributes(input: &syn::ItemFn) {
-    for attribute in &input.attrs {
-        let meta = attribute.parse_meta();
-        match meta {
-            std::result::Result::Ok(m) => match m {
-                syn::Meta::Path(p) => {
-                    if p.segments.iter().any(|ps| ps.ident == "timeout") {
-                        panic!("Timeout attribute is only allowed once");
+// Adds the timeout attribute of the module to all test functions of the module which do not define
+// their own timeout. Nested inline modules are processed recursively unless they have their own timeout.
+fn add_module_timeout(items: &mut [syn::Item], timeout_attribute: &syn::Attribute) {
+    for item in items {
+        match item {
+            syn::Item::Fn(function) => {
+                if function.attrs.iter().any(is_test_case_timeout_attribute) {
+                    // The test cases have their own default timeout
+                } else if function.attrs.iter().any(is_test_case_attribute) {
+                    // Attributes after a test case only apply to this test case.
+                    // Add the timeout to every test case which does not define its own timeout.
+                    let mut attrs = vec![];
+                    let mut case_has_timeout = true;
+                    for attribute in function.attrs.drain(..) {
+                        if is_test_case_attribute(&attribute) {
+                            if !case_has_timeout {
+                                attrs.push(timeout_attribute.clone());
+                            }
+                            case_has_timeout = test_case_has_timeout(&attribute);
+                        }
+                        case_has_timeout |= is_timeout_attribute(&attribute);
+                        attrs.push(attribute);
                     }
-                }
-                syn::Meta::List(ml) => {
-                    if ml.path.segments.iter().any(|ps| ps.ident == "timeout") {
-                        panic!("Timeout attribute is only allowed once");
+                    if !case_has_timeout {
+                        attrs.push(timeout_attribute.clone());
                     }
+                    function.attrs = attrs;
+                } else if (function.attrs.iter().any(is_test_attribute)
+                    || function.attrs.iter().any(is_test_case_types_attribute))
+                    && !function.attrs.iter().any(is_timeout_attribute)
+                {
+                    function.attrs.push(timeout_attribute.clone());
                 }
-                syn::Meta::NameValue(nv) => {
-                    if nv.path.segments.iter().any(|ps| ps.ident == "timeout") {
-                        panic!("Timeout attribute is only allowed once");
-                    }
+            }
+            syn::Item::Mod(module) => {
+                if module.attrs.iter().any(is_timeout_attribute) {
+                    continue;
                 }
-            },
-            Err(e) => panic!("Could not determine meta data. Error {}.", e),
+                if let Some((_, items)) = &mut module.content {
+                    add_module_timeout(items, timeout_attribute);
+                }
+            }
+            _ => {}
         }
     }
 }
 
-fn get_timeout(attribute_args: &syn::AttributeArgs) -> u64 {
-    if attribute_args.len() > 1 {
-        panic!("Only one integer expected. Example: #[timeout(10)]");
+// Finds a timeout argument such as a constant which is not a literal
+fn non_literal_argument(args: &Punctuated<syn::Expr, syn::Token![,]>) -> Option<&syn::Expr> {
+    args.iter()
+        .map(|arg| match arg {
+            syn::Expr::Assign(assign) => &*assign.right,
+            expr => expr,
+        })
+        .find(|expr| !matches!(expr, syn::Expr::Lit(_)))
+}
+
+// The expression of a module timeout may not be in scope within nested modules
+fn check_nested_modules(items: &[syn::Item], expr: &syn::Expr, errors: &mut Vec<syn::Error>) {
+    for item in items {
+        match item {
+            syn::Item::Mod(module)
+                if module.content.is_some() && !module.attrs.iter().any(is_timeout_attribute) =>
+            {
+                errors.push(syn::Error::new_spanned(
+                    &module.ident,
+                    format!("The module timeout `{}` is not a literal and can not be applied to nested modules since it may not be in scope there. Add a timeout attribute to the nested module.", quote! { #expr }),
+                ));
+            }
+            _ => {}
+        }
     }
-    match &attribute_args[0] {
-        syn::NestedMeta::Meta(_) => {
-            panic!("Integer expected. Example: #[timeout(10)]");
+}
+
+#[derive(Default)]
+struct TimeoutArgs {
+    fail: Option<proc_macro2::TokenStream>,
+    warn: Option<proc_macro2::TokenStream>,
+    cpu: Option<proc_macro2::TokenStream>,
+    retries: Option<u32>,
+}
+
+fn parse_timeout_args(
+    attribute_args: &Punctuated<syn::Expr, syn::Token![,]>,
+    errors: &mut Vec<syn::Error>,
+) -> TimeoutArgs {
+    let mut timeout_args = TimeoutArgs::default();
+    let mut has_timeout = false;
+    let mut fail_value = None;
+    let mut warn_value = None;
+    for (i, arg) in attribute_args.iter().enumerate() {
+        let (target, value) = match arg {
+            syn::Expr::Assign(assign) => {
+                let key = match &*assign.left {
+                    syn::Expr::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+                    _ => None,
+                };
+                let target = match key.as_deref() {
+                    Some("fail") => {
+                        fail_value = Some(&*assign.right);
+                        &mut timeout_args.fail
+                    }
+                    Some("warn") => {
+                        warn_value = Some(&*assign.right);
+                        &mut timeout_args.warn
+                    }
+                    Some("cpu") => &mut timeout_args.cpu,
+                    Some("retries") => {
+                        if timeout_args.retries.is_some() {
+                            errors.push(syn::Error::new_spanned(
+                                assign,
+                                "Each timeout argument is only allowed once",
+                            ));
+                        }
+                        match get_retries(&assign.right) {
+                            Ok(retries) => timeout_args.retries = Some(retries),
+                            Err(e) => errors.push(e),
+                        }
+                        continue;
+                    }
+                    _ => {
+                        errors.push(syn::Error::new_spanned(&assign.left, "Unexpected timeout argument. Expected `fail`, `warn`, `cpu`, or `retries`. Example: #[timeout(warn = \"200ms\", fail = \"1s\")]"));
+                        continue;
+                    }
+                };
+                if target.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        assign,
+                        "Each timeout argument is only allowed once",
+                    ));
+                    continue;
+                }
+                (target, &*assign.right)
+            }
+            expr if i == 0 => {
+                fail_value = Some(expr);
+                (&mut timeout_args.fail, expr)
+            }
+            expr => {
+                errors.push(syn::Error::new_spanned(expr, "Only the first timeout argument can be given without a name. Example: #[timeout(1000, warn = \"200ms\")]"));
+                continue;
+            }
+        };
+        has_timeout = true;
+        match get_timeout(value) {
+            Ok(value) => *target = Some(value),
+            Err(e) => errors.push(e),
         }
-        syn::NestedMeta::Lit(lit) => match lit {
-            syn::Lit::Int(int) => int.base10_parse::<u64>().expect("Integer expected"),
-            _ => {
-                panic!("Integer as timeout in ms expected. Example: #[timeout(10)]");
+    }
+    if let (Some(warn), Some(fail)) = (warn_value, fail_value) {
+        if let (Some(warn_duration), Some(fail_duration)) =
+            (literal_duration(warn), literal_duration(fail))
+        {
+            if warn_duration >= fail_duration {
+                errors.push(syn::Error::new_spanned(
+                    warn,
+                    "The soft timeout must be shorter than the timeout. Example: #[timeout(warn = \"200ms\", fail = \"1s\")]",
+                ));
             }
-        },
+        }
+    }
+    if !has_timeout {
+        errors.push(syn::Error::new(Span::call_site(), "Timeout expected. Example: #[timeout(10)], #[timeout(\"2s\")], #[timeout(cpu = \"500ms\")], or #[timeout(warn = \"200ms\", fail = \"1s\")]"));
+    }
+    timeout_args
+}
+
+fn timeout_spec(
+    timeout_args: &TimeoutArgs,
+    ident: &syn::Ident,
+    ntest: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let fail = option_tokens(&timeout_args.fail);
+    let warn = option_tokens(&timeout_args.warn);
+    let cpu = option_tokens(&timeout_args.cpu);
+    let retries = timeout_args.retries.unwrap_or(0);
+    quote! {
+        #ntest::TimeoutSpec {
+            path: concat!(module_path!(), "::", stringify!(#ident)),
+            fail: #fail,
+            warn: #warn,
+            cpu: #cpu,
+            // Only set by cargo when integration tests are compiled
+            integration: option_env!("CARGO_TARGET_TMPDIR").is_some(),
+            retries: #retries,
+        }
+    }
+}
+
+// Closure which describes a returned `Result::Err` as failure of the test. The type of the output
+// must be known to select the description of a `Result`. Opaque types are never described.
+fn output_failure(
+    ntest: &proc_macro2::TokenStream,
+    output: &syn::ReturnType,
+) -> proc_macro2::TokenStream {
+    let ty = match output {
+        syn::ReturnType::Type(_, ty) if mentions(quote! { #ty }, "impl") => {
+            return quote! { |_| std::option::Option::None };
+        }
+        syn::ReturnType::Type(_, ty) => quote! { #ty },
+        syn::ReturnType::Default => quote! { () },
+    };
+    quote! {
+        |output: &#ty| {
+            use #ntest::{ErrOutput as _, PlainOutput as _};
+            (&#ntest::TestOutput(output)).ntest_failure()
+        }
+    }
+}
+
+fn option_tokens(value: &Option<proc_macro2::TokenStream>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { std::option::Option::Some(#value) },
+        None => quote! { std::option::Option::None },
+    }
+}
+
+fn get_timeout(timeout: &syn::Expr) -> syn::Result<proc_macro2::TokenStream> {
+    match timeout {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => {
+            let ms = int.base10_parse::<u64>().map_err(|_| {
+                syn::Error::new_spanned(
+                    int,
+                    "Integer as timeout in ms expected. Example: #[timeout(10)]",
+                )
+            })?;
+            Ok(quote_spanned! {int.span()=> std::time::Duration::from_millis(#ms) })
+        }
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => {
+            let duration =
+                duration::parse_duration(&s.value()).map_err(|e| syn::Error::new_spanned(s, e))?;
+            let secs = duration.as_secs();
+            let nanos = duration.subsec_nanos();
+            Ok(quote_spanned! {s.span()=> std::time::Duration::new(#secs, #nanos) })
+        }
+        syn::Expr::Lit(lit) => Err(syn::Error::new_spanned(
+            lit,
+            "Integer in ms or duration string expected. Example: #[timeout(10)] or #[timeout(\"2s\")]",
+        )),
+        expr => Ok(quote_spanned! {expr.span()=> {
+            const NTEST_TIMEOUT: std::time::Duration = #expr;
+            NTEST_TIMEOUT
+        }}),
+    }
+}
+
+// Duration of an integer or string literal. Constant expressions are only known to the compiler.
+fn literal_duration(timeout: &syn::Expr) -> Option<std::time::Duration> {
+    match timeout {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int
+            .base10_parse::<u64>()
+            .ok()
+            .map(std::time::Duration::from_millis),
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => duration::parse_duration(&s.value()).ok(),
+        _ => None,
+    }
+}
+
+fn get_retries(retries: &syn::Expr) -> syn::Result<u32> {
+    match retries {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_parse::<u32>(),
+        expr => Err(syn::Error::new_spanned(
+            expr,
+            "Integer as number of retries expected. Example: #[retry(3)] or #[timeout(1000, retries = 2)]",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_messages(result: syn::Result<proc_macro2::TokenStream>) -> Vec<String> {
+        result
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reports_all_argument_errors() {
+        let messages = error_messages(expand_timeout(
+            quote! { 10, 20, slow = 5, warn = "1 lightyear" },
+            quote! { fn test() {} },
+        ));
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].starts_with("Only the first timeout argument"));
+        assert!(messages[1].starts_with("Unexpected timeout argument"));
+        assert!(messages[2].contains("lightyear"));
+    }
+
+    #[test]
+    fn reports_soft_timeout_not_below_timeout() {
+        let messages = error_messages(expand_timeout(
+            quote! { "1s", warn = 1000 },
+            quote! { fn test() {} },
+        ));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("The soft timeout must be shorter"));
+        assert!(expand_timeout(quote! { 1000, warn = "999ms" }, quote! { fn test() {} }).is_ok());
+    }
+
+    #[test]
+    fn reports_duplicate_timeout_attribute() {
+        let messages = error_messages(expand_timeout(
+            quote! { 10 },
+            quote! {
+                #[timeout(20)]
+                fn test() {}
+            },
+        ));
+        assert_eq!(messages, ["Timeout attribute is only allowed once"]);
+    }
+
+    #[test]
+    fn reports_missing_timeout() {
+        let messages = error_messages(expand_timeout(quote! {}, quote! { fn test() {} }));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("Timeout expected"));
+    }
+
+    #[test]
+    fn reports_all_retry_errors() {
+        let messages = error_messages(expand_retry(
+            quote! { "3" },
+            quote! {
+                #[timeout(10, retries = 2)]
+                #[timeout(20)]
+                fn test() {}
+            },
+        ));
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0], "Timeout attribute is only allowed once");
+        assert!(messages[1].starts_with("Integer as number of retries expected"));
+    }
+
+    #[test]
+    fn reports_retries_given_twice() {
+        let messages = error_messages(expand_timeout(
+            quote! { 10, retries = 2 },
+            quote! {
+                #[retry(3)]
+                fn test() {}
+            },
+        ));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0]
+            .starts_with("Retries are given by the timeout attribute and the retry attribute"));
+    }
+
+    #[test]
+    fn reports_missing_timeout_with_retries() {
+        let messages = error_messages(expand_timeout(
+            quote! { retries = 2 },
+            quote! { fn test() {} },
+        ));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("Timeout expected"));
+    }
+
+    #[test]
+    fn module_timeout_skips_test_cases_with_timeout() {
+        let mut items: Vec<syn::Item> = vec![syn::parse_quote! {
+            #[test_case(1 => 1, timeout = "1s")]
+            #[test_case(2 => 2)]
+            fn test(x: u32) -> u32 { x }
+        }];
+        add_module_timeout(&mut items, &syn::parse_quote! { #[timeout(100)] });
+        let attrs = match &items[0] {
+            syn::Item::Fn(function) => &function.attrs,
+            _ => unreachable!(),
+        };
+        assert_eq!(attrs.iter().filter(|a| is_timeout_attribute(a)).count(), 1);
+        assert!(is_timeout_attribute(&attrs[2]));
+    }
+
+    #[test]
+    fn module_timeout_adds_timeout_to_test_case_types() {
+        let mut items: Vec<syn::Item> = vec![syn::parse_quote! {
+            #[test_case_types(u8, u16)]
+            fn test<T>() {}
+        }];
+        add_module_timeout(&mut items, &syn::parse_quote! { #[timeout(100)] });
+        let attrs = match &items[0] {
+            syn::Item::Fn(function) => &function.attrs,
+            _ => unreachable!(),
+        };
+        assert_eq!(attrs.len(), 2);
+        assert!(is_timeout_attribute(&attrs[1]));
+    }
+
+    #[test]
+    fn reports_constant_timeout_of_nested_module() {
+        let messages = error_messages(expand_timeout(
+            quote! { BUDGET },
+            quote! {
+                mod tests {
+                    mod nested {}
+                    #[timeout(100)]
+                    mod with_own_timeout {}
+                }
+            },
+        ));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("The module timeout `BUDGET` is not a literal"));
+    }
+
+    #[test]
+    fn reports_all_isolated_errors() {
+        let messages = error_messages(expand_isolated(
+            quote! { 10 },
+            quote! { fn test(x: u32) {} },
+        ));
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("does not take any arguments"));
+        assert!(messages[1].contains("without parameters"));
     }
 }
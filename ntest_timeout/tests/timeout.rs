@@ -1,4 +1,7 @@
-use ntest_timeout::timeout;
+#![allow(clippy::empty_loop)]
+
+use ntest_timeout::{retry, timeout};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::{thread, time};
 
 #[test]
@@ -73,3 +76,298 @@ fn should_panic_with_expected_syntax() {
 fn should_panic_with_message_reversed_order() {
     panic!("error 123")
 }
+
+const FIFTY_MILLIS: time::Duration = time::Duration::from_millis(50);
+
+#[test]
+#[timeout("1s")]
+fn duration_string_no_timeout() {
+    thread::sleep(time::Duration::from_millis(10));
+}
+
+#[test]
+#[timeout("10ms")]
+#[should_panic]
+fn duration_string_timeout() {
+    thread::sleep(time::Duration::from_millis(500));
+}
+
+#[test]
+#[timeout("1m30s")]
+fn duration_string_combined_units() {}
+
+#[test]
+#[timeout(FIFTY_MILLIS)]
+#[should_panic]
+fn duration_constant_timeout() {
+    thread::sleep(time::Duration::from_millis(500));
+}
+
+#[test]
+#[timeout(time::Duration::from_secs(5))]
+fn duration_expression_no_timeout() {}
+
+#[test]
+#[timeout(warn = "1ms", fail = "1s")]
+fn soft_timeout_exceeded() {
+    thread::sleep(time::Duration::from_millis(10));
+}
+
+#[test]
+#[timeout(1000, warn = 1)]
+fn soft_timeout_with_positional_fail() {
+    thread::sleep(time::Duration::from_millis(10));
+}
+
+#[test]
+#[timeout(warn = "1ms")]
+fn soft_timeout_only() {
+    thread::sleep(time::Duration::from_millis(10));
+}
+
+#[test]
+#[timeout(warn = "5ms", fail = "10ms")]
+#[should_panic]
+fn soft_timeout_with_hard_timeout_exceeded() {
+    thread::sleep(time::Duration::from_millis(500));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+#[timeout(cpu = "100ms")]
+fn cpu_timeout_ignores_sleep() {
+    thread::sleep(time::Duration::from_millis(300));
+}
+
+#[test]
+#[timeout(cpu = "50ms")]
+#[should_panic(expected = "timeout")]
+fn cpu_timeout_exceeded() {
+    loop {
+        ntest::check_cancelled();
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+#[timeout(fail = "50ms", cpu = "1s")]
+#[should_panic(expected = "of CPU time. Max time 50 ms (annotated 50 ms)")]
+fn cpu_timeout_with_wall_clock_exceeded() {
+    thread::sleep(time::Duration::from_millis(500));
+}
+
+trait Shape {
+    fn area(&self) -> f64;
+}
+
+struct Square(f64);
+
+impl Shape for Square {
+    fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+}
+
+#[timeout(100)]
+fn check_area<T>(shape: T, expected: f64) -> f64
+where
+    T: Shape + Send,
+{
+    assert_eq!(shape.area(), expected);
+    shape.area()
+}
+
+#[timeout(100)]
+fn longest<'a>(words: &'a [&'a str]) -> &'a str {
+    words.iter().max_by_key(|word| word.len()).unwrap()
+}
+
+#[timeout(100)]
+fn parse_sum(values: &[&str]) -> Result<u32, std::num::ParseIntError> {
+    let mut sum = 0;
+    for value in values {
+        sum += value.parse::<u32>()?;
+    }
+    Ok(sum)
+}
+
+#[timeout(10)]
+fn spin(iterations: &mut u64) {
+    loop {
+        *iterations += 1;
+        ntest::check_cancelled();
+    }
+}
+
+#[timeout(10)]
+fn blocking_sleep(ms: u64) {
+    thread::sleep(time::Duration::from_millis(ms));
+}
+
+impl Square {
+    #[timeout(100)]
+    fn scaled(&self, factor: f64) -> Square {
+        Square(self.0 * factor)
+    }
+
+    #[timeout(100)]
+    fn into_side(self) -> f64 {
+        self.0
+    }
+}
+
+struct Wrapper<T>(T);
+
+impl<T: Default + Send> Wrapper<T> {
+    #[timeout(100)]
+    fn make() -> Self {
+        Wrapper(T::default())
+    }
+}
+
+impl Square {
+    #[timeout(100)]
+    fn unit() -> Self {
+        Square(1.0)
+    }
+}
+
+#[test]
+fn timeout_on_generic_function() {
+    assert_eq!(check_area(Square(2.0), 4.0), 4.0);
+}
+
+#[test]
+fn timeout_on_function_with_borrowed_arguments() {
+    let words = vec!["a", "abc", "ab"];
+    assert_eq!(longest(&words), "abc");
+    assert_eq!(parse_sum(&["1", "2"]), Ok(3));
+    assert!(parse_sum(&["1", "x"]).is_err());
+}
+
+#[test]
+fn timeout_on_methods() {
+    let square = Square(2.0).scaled(1.5);
+    assert_eq!(square.into_side(), 3.0);
+}
+
+#[test]
+#[should_panic(expected = "ignored the cancellation and finished after")]
+fn timeout_on_blocking_function_with_arguments() {
+    blocking_sleep(300);
+}
+
+#[test]
+fn timeout_on_associated_functions_returning_self() {
+    assert_eq!(Square::unit().area(), 1.0);
+    assert_eq!(Wrapper::<u32>::make().0, 0);
+}
+
+#[test]
+#[should_panic(expected = "timeout: the function call took")]
+fn timeout_on_function_with_arguments_exceeded() {
+    let mut iterations = 0;
+    spin(&mut iterations);
+}
+
+static TIMED_OUT_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[test]
+#[timeout(50, retries = 2)]
+fn retries_timed_out_attempt() {
+    if TIMED_OUT_ATTEMPTS.fetch_add(1, Ordering::SeqCst) == 0 {
+        thread::sleep(time::Duration::from_millis(500));
+    }
+}
+
+static FAILED_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[test]
+#[retry(3)]
+fn retries_failed_attempt() {
+    assert!(FAILED_ATTEMPTS.fetch_add(1, Ordering::SeqCst) >= 2);
+}
+
+static ERROR_ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+
+#[test]
+#[retry(1)]
+fn retries_attempt_returning_error() -> Result<(), String> {
+    match ERROR_ATTEMPTS.fetch_add(1, Ordering::SeqCst) {
+        0 => Err("first attempt".to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+#[timeout(100)]
+fn error_of_helper_function_is_returned() {
+    #[timeout(100)]
+    fn parse(value: &str) -> Result<u32, std::num::ParseIntError> {
+        value.parse()
+    }
+    assert!(parse("x").is_err());
+    assert_eq!(parse("42"), Ok(42));
+}
+
+#[test]
+#[retry(1)]
+#[timeout(10)]
+#[should_panic(expected = "test failed in all 2 attempts:\nattempt 1/2: timeout after")]
+fn retries_exhausted() {
+    thread::sleep(time::Duration::from_millis(500));
+}
+
+#[timeout(100)]
+#[retry(2)]
+fn divide(dividend: u32, divisor: u32) -> u32 {
+    dividend / divisor
+}
+
+#[test]
+fn retries_function_with_arguments() {
+    assert_eq!(divide(6, 3), 2);
+}
+
+#[timeout(100)]
+mod module_timeout {
+    use ntest_timeout::timeout;
+    use std::{thread, time};
+
+    #[test]
+    fn no_timeout() {
+        thread::sleep(time::Duration::from_millis(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Max time 100 ms")]
+    fn timeout() {
+        thread::sleep(time::Duration::from_millis(500));
+    }
+
+    #[test]
+    #[timeout(1000)]
+    fn override_timeout() {
+        thread::sleep(time::Duration::from_millis(200));
+    }
+
+    mod nested {
+        use std::{thread, time};
+
+        #[test]
+        #[should_panic(expected = "Max time 100 ms")]
+        fn nested_timeout() {
+            thread::sleep(time::Duration::from_millis(500));
+        }
+    }
+
+    #[timeout(1000)]
+    mod nested_override {
+        use std::{thread, time};
+
+        #[test]
+        fn nested_override_timeout() {
+            thread::sleep(time::Duration::from_millis(200));
+        }
+    }
+}